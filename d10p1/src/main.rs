@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::error::Error;
 use std::fs;
 
@@ -8,9 +9,351 @@ struct Coordinate {
 	y: u32,
 }
 
+impl Ord for Coordinate {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		self.x.cmp(&other.x).then_with(|| self.y.cmp(&other.y))
+	}
+}
+
+impl PartialOrd for Coordinate {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+struct LoopAnalysis {
+	start_shape: char,
+	loop_tiles: Vec<Coordinate>,
+	interior_tiles: Vec<Coordinate>,
+}
+
+impl LoopAnalysis {
+	fn farthest_distance(&self) -> usize {
+		self.loop_tiles.len() / 2
+	}
+
+	fn enclosed_count(&self) -> usize {
+		enclosed_count(&self.loop_tiles)
+	}
+}
+
+/// Counts tiles enclosed by a loop given in order, using the shoelace area of the loop and Pick's theorem.
+fn enclosed_count(loop_tiles: &[Coordinate]) -> usize {
+	let mut double_area: i64 = 0;
+	for (tile, next_tile) in loop_tiles.iter().zip(loop_tiles.iter().cycle().skip(1)) {
+		double_area += tile.x as i64 * next_tile.y as i64 - next_tile.x as i64 * tile.y as i64;
+	}
+	(double_area.unsigned_abs() as usize + 2 - loop_tiles.len()) / 2
+}
+
+enum BrokenConnection {
+	OffGrid(Coordinate),
+	NotReciprocated(Coordinate, Coordinate),
+}
+
+struct PipeComponent {
+	tiles: Vec<Coordinate>,
+	closed_loop: Option<Vec<Coordinate>>,
+}
+
+/// Checks every pipe in the map, not just those on the loop through S, for connections that lead nowhere and for
+/// groups of pipes that join up with each other.
+fn diagnose(
+	pipe_tiles: &HashMap<Coordinate, Vec<Coordinate>>,
+	width: u32,
+	height: u32,
+) -> (Vec<BrokenConnection>, Vec<PipeComponent>) {
+	let mut pipes: Vec<&Coordinate> = pipe_tiles.keys().collect();
+	pipes.sort_unstable_by_key(|tile| (tile.y, tile.x));
+
+	let mut broken_connections: Vec<BrokenConnection> = Vec::new();
+	let mut linked_tiles: HashMap<Coordinate, Vec<Coordinate>> = HashMap::new();
+	for pipe in pipes.iter() {
+		let connections = &pipe_tiles[*pipe];
+		let mut links: Vec<Coordinate> = Vec::new();
+		let mut off_grid = connections.len() < 2;
+		for connection in connections.iter() {
+			if connection.x >= width || connection.y >= height {
+				off_grid = true;
+			} else if pipe_tiles
+				.get(connection)
+				.is_some_and(|connection_links| connection_links.contains(pipe))
+			{
+				links.push(*connection);
+			} else {
+				broken_connections.push(BrokenConnection::NotReciprocated(**pipe, *connection));
+			}
+		}
+		if off_grid {
+			broken_connections.push(BrokenConnection::OffGrid(**pipe));
+		}
+		linked_tiles.insert(**pipe, links);
+	}
+
+	let mut visited: HashSet<Coordinate> = HashSet::new();
+	let mut components: Vec<PipeComponent> = Vec::new();
+	for pipe in pipes {
+		if !visited.insert(*pipe) {
+			continue;
+		}
+		let mut tiles: Vec<Coordinate> = vec![*pipe];
+		let mut current_tiles = vec![*pipe];
+		while let Some(tile) = current_tiles.pop() {
+			for linked_tile in linked_tiles[&tile].iter() {
+				if visited.insert(*linked_tile) {
+					tiles.push(*linked_tile);
+					current_tiles.push(*linked_tile);
+				}
+			}
+		}
+
+		let closed_loop = if tiles.len() > 2 && tiles.iter().all(|tile| linked_tiles[tile].len() == 2) {
+			let mut loop_tiles = vec![*pipe];
+			let mut previous_tile = *pipe;
+			let mut current_tile = linked_tiles[pipe][0];
+			while current_tile != *pipe {
+				loop_tiles.push(current_tile);
+				let next_tile = *linked_tiles[&current_tile]
+					.iter()
+					.find(|tile| **tile != previous_tile)
+					.unwrap();
+				previous_tile = current_tile;
+				current_tile = next_tile;
+			}
+			Some(loop_tiles)
+		} else {
+			None
+		};
+
+		components.push(PipeComponent { tiles, closed_loop });
+	}
+
+	(broken_connections, components)
+}
+
+fn pipe_shape(tile: &Coordinate, connections: &[Coordinate]) -> Option<char> {
+	let north = tile.y > 0
+		&& connections.contains(&Coordinate {
+			x: tile.x,
+			y: tile.y - 1,
+		});
+	let south = connections.contains(&Coordinate {
+		x: tile.x,
+		y: tile.y + 1,
+	});
+	let west = tile.x > 0
+		&& connections.contains(&Coordinate {
+			x: tile.x - 1,
+			y: tile.y,
+		});
+	let east = connections.contains(&Coordinate {
+		x: tile.x + 1,
+		y: tile.y,
+	});
+	match (north, south, west, east) {
+		(true, true, false, false) => Some('|'),
+		(false, false, true, true) => Some('-'),
+		(true, false, false, true) => Some('L'),
+		(true, false, true, false) => Some('J'),
+		(false, true, true, false) => Some('7'),
+		(false, true, false, true) => Some('F'),
+		_ => None,
+	}
+}
+
+/// Walks the loop from `start_tile` through `first_tile`, giving the loop's tiles in order if it closes.
+fn walk_loop(
+	pipe_tiles: &HashMap<Coordinate, Vec<Coordinate>>,
+	start_tile: Coordinate,
+	first_tile: Coordinate,
+) -> Result<Vec<Coordinate>, String> {
+	let mut loop_tiles = vec![start_tile];
+	let mut previous_tile = start_tile;
+	let mut current_tile = first_tile;
+	while current_tile != start_tile {
+		let Some(connections) = pipe_tiles.get(&current_tile) else {
+			return Err(format!(
+				"Loop from S breaks at ({}, {}), which isn't a pipe",
+				current_tile.x, current_tile.y
+			));
+		};
+		if !connections.contains(&previous_tile) {
+			return Err(format!(
+				"Loop from S breaks at ({}, {}), which doesn't connect back to ({}, {})",
+				current_tile.x, current_tile.y, previous_tile.x, previous_tile.y
+			));
+		}
+		let Some(next_tile) = connections.iter().find(|tile| **tile != previous_tile).copied() else {
+			return Err(format!(
+				"Loop from S dead-ends at ({}, {})",
+				current_tile.x, current_tile.y
+			));
+		};
+		loop_tiles.push(current_tile);
+		previous_tile = current_tile;
+		current_tile = next_tile;
+	}
+	if !pipe_tiles[&start_tile].contains(&previous_tile) {
+		return Err(String::from("Loop doesn't reach S from a direction S connects to"));
+	}
+	Ok(loop_tiles)
+}
+
+/// Infers the shape of the start tile from its neighbours and walks the main loop through it once.
+fn analyse_loop(
+	pipe_tiles: &mut HashMap<Coordinate, Vec<Coordinate>>,
+	start_tile: Coordinate,
+) -> Result<LoopAnalysis, Box<dyn Error>> {
+	let mut candidates: Vec<Coordinate> = pipe_tiles
+		.iter()
+		.filter(|(_, connections)| connections.contains(&start_tile))
+		.map(|(pipe, _)| *pipe)
+		.collect();
+	candidates.sort_unstable();
+
+	let mut last_error = String::from("S doesn't connect to two pipes");
+	for (index, first_tile) in candidates.iter().enumerate() {
+		for second_tile in candidates.iter().skip(index + 1) {
+			let Some(start_shape) = pipe_shape(&start_tile, &[*first_tile, *second_tile]) else {
+				continue;
+			};
+			pipe_tiles.insert(start_tile, vec![*first_tile, *second_tile]);
+			match walk_loop(pipe_tiles, start_tile, *first_tile) {
+				Ok(loop_tiles) => {
+					let interior_tiles = interior_tiles(pipe_tiles, &loop_tiles);
+					return Ok(LoopAnalysis {
+						start_shape,
+						loop_tiles,
+						interior_tiles,
+					});
+				}
+				Err(error) => last_error = error,
+			}
+		}
+	}
+	pipe_tiles.remove(&start_tile);
+	Err(format!("S isn't on a closed loop: {}", last_error).into())
+}
+
+/// Finds tiles inside the loop by counting crossings of north-connected loop tiles along each row.
+fn interior_tiles(pipe_tiles: &HashMap<Coordinate, Vec<Coordinate>>, loop_tiles: &[Coordinate]) -> Vec<Coordinate> {
+	let loop_set: HashSet<Coordinate> = loop_tiles.iter().copied().collect();
+	let min_x = loop_tiles.iter().map(|tile| tile.x).min().unwrap();
+	let max_x = loop_tiles.iter().map(|tile| tile.x).max().unwrap();
+	let min_y = loop_tiles.iter().map(|tile| tile.y).min().unwrap();
+	let max_y = loop_tiles.iter().map(|tile| tile.y).max().unwrap();
+
+	let mut interior: Vec<Coordinate> = Vec::new();
+	for y in min_y..=max_y {
+		let mut inside = false;
+		for x in min_x..=max_x {
+			let coord = Coordinate { x, y };
+			if loop_set.contains(&coord) {
+				if y > 0 && pipe_tiles[&coord].contains(&Coordinate { x, y: y - 1 }) {
+					inside = !inside;
+				}
+			} else if inside {
+				interior.push(coord);
+			}
+		}
+	}
+	interior
+}
+
+fn box_glyph(tile: &Coordinate, connections: &[Coordinate]) -> char {
+	let north = tile.y > 0
+		&& connections.contains(&Coordinate {
+			x: tile.x,
+			y: tile.y - 1,
+		});
+	let south = connections.contains(&Coordinate {
+		x: tile.x,
+		y: tile.y + 1,
+	});
+	let west = tile.x > 0
+		&& connections.contains(&Coordinate {
+			x: tile.x - 1,
+			y: tile.y,
+		});
+	let east = connections.contains(&Coordinate {
+		x: tile.x + 1,
+		y: tile.y,
+	});
+	match (north, south, west, east) {
+		(false, false, false, false) => '·',
+		(true, false, false, false) => '╵',
+		(false, true, false, false) => '╷',
+		(false, false, true, false) => '╴',
+		(false, false, false, true) => '╶',
+		(true, true, false, false) => '│',
+		(false, false, true, true) => '─',
+		(true, false, false, true) => '└',
+		(true, false, true, false) => '┘',
+		(false, true, true, false) => '┐',
+		(false, true, false, true) => '┌',
+		(true, true, false, true) => '├',
+		(true, true, true, false) => '┤',
+		(false, true, true, true) => '┬',
+		(true, false, true, true) => '┴',
+		(true, true, true, true) => '┼',
+	}
+}
+
+/// Draws the maze with box-drawing pipes: the main loop in bold cyan, junk pipes dimmed, inside tiles as yellow
+/// blocks and outside tiles as dim dots.
+fn render(
+	pipe_tiles: &HashMap<Coordinate, Vec<Coordinate>>,
+	analysis: &LoopAnalysis,
+	width: u32,
+	height: u32,
+) -> String {
+	let loop_set: HashSet<Coordinate> = analysis.loop_tiles.iter().copied().collect();
+	let interior_set: HashSet<Coordinate> = analysis.interior_tiles.iter().copied().collect();
+	let start_tile = analysis.loop_tiles[0];
+
+	let mut output = String::new();
+	for y in 0..height {
+		for x in 0..width {
+			let coord = Coordinate { x, y };
+			if loop_set.contains(&coord) {
+				let code = if coord == start_tile { "1;35" } else { "1;36" };
+				let glyph = box_glyph(&coord, &pipe_tiles[&coord]);
+				output.push_str(&format!("\x1b[{}m{}\x1b[0m", code, glyph));
+			} else if interior_set.contains(&coord) {
+				output.push_str("\x1b[33m█\x1b[0m");
+			} else if let Some(connections) = pipe_tiles.get(&coord) {
+				output.push_str(&format!("\x1b[2m{}\x1b[0m", box_glyph(&coord, connections)));
+			} else {
+				output.push_str("\x1b[2;34m·\x1b[0m");
+			}
+		}
+		output.push('\n');
+	}
+	output
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
-	let (start_tile, pipe_tiles) = {
+	let mut count_enclosed = false;
+	let mut show_summary = false;
+	let mut show_interior = false;
+	let mut show_render = false;
+	let mut show_diagnostics = false;
+	for arg in env::args().skip(1) {
+		match arg.as_str() {
+			// Part two of the puzzle asks for the number of tiles enclosed by the loop
+			"--enclosed" => count_enclosed = true,
+			"--summary" => show_summary = true,
+			"--interior" => show_interior = true,
+			"--render" => show_render = true,
+			"--diagnose" => show_diagnostics = true,
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
+	let (start_tile, mut pipe_tiles, width, height) = {
 		let input = fs::read_to_string("input.txt")?;
+		let width = input.lines().map(|line| line.len()).max().unwrap_or(0) as u32;
+		let height = input.lines().count() as u32;
 
 		let mut start_tile: Option<Coordinate> = None;
 		let mut pipe_tiles: HashMap<Coordinate, Vec<Coordinate>> = HashMap::new();
@@ -64,9 +407,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 						pipe_tiles.insert(Coordinate { x, y }, connects_to);
 					}
 					'F' => {
-						let mut connects_to = Vec::new();
-						connects_to.push(Coordinate { x: x + 1, y });
-						connects_to.push(Coordinate { x, y: y + 1 });
+						let connects_to = vec![Coordinate { x: x + 1, y }, Coordinate { x, y: y + 1 }];
 						pipe_tiles.insert(Coordinate { x, y }, connects_to);
 					}
 					'.' => (),
@@ -76,47 +417,76 @@ fn main() -> Result<(), Box<dyn Error>> {
 			}
 		}
 
-		let start_tile = start_tile.unwrap();
-		let mut start_tile_connects_to: Vec<Coordinate> = Vec::new();
-		for (pipe, connections) in pipe_tiles.iter() {
-			if connections.contains(&start_tile) {
-				start_tile_connects_to.push(*pipe);
-			}
-		}
-		pipe_tiles.insert(start_tile, start_tile_connects_to);
+		let Some(start_tile) = start_tile else {
+			return Err("Map has no S tile".into());
+		};
 
-		(start_tile, pipe_tiles)
+		(start_tile, pipe_tiles, width, height)
 	};
 
-	let mut pipe_distances: HashMap<Coordinate, u32> = HashMap::new();
-	pipe_distances.insert(start_tile, 0);
-
-	let mut current_distance = 0;
-	let mut current_tiles = vec![start_tile];
+	let analysis = analyse_loop(&mut pipe_tiles, start_tile);
 
-	loop {
-		let mut next_tiles = Vec::new();
-		for tile in current_tiles.iter() {
-			let connected_tiles = pipe_tiles.get(tile).unwrap();
-			for connected_tile in connected_tiles.iter() {
-				if !pipe_distances.contains_key(connected_tile) {
-					next_tiles.push(*connected_tile);
+	if show_diagnostics {
+		let (broken_connections, components) = diagnose(&pipe_tiles, width, height);
+		for broken_connection in broken_connections.iter() {
+			match broken_connection {
+				BrokenConnection::OffGrid(tile) => {
+					println!("Broken: ({}, {}) points off the grid", tile.x, tile.y)
 				}
+				BrokenConnection::NotReciprocated(tile, connection) => println!(
+					"Broken: ({}, {}) points to ({}, {}), which doesn't point back",
+					tile.x, tile.y, connection.x, connection.y
+				),
 			}
 		}
-
-		if next_tiles.is_empty() {
-			break;
+		for (index, component) in components.iter().enumerate() {
+			let first_tile = component.tiles[0];
+			match &component.closed_loop {
+				Some(loop_tiles) => println!(
+					"Component {} from ({}, {}): closed loop of length {} enclosing {} tiles",
+					index + 1,
+					first_tile.x,
+					first_tile.y,
+					loop_tiles.len(),
+					enclosed_count(loop_tiles)
+				),
+				None => println!(
+					"Component {} from ({}, {}): {} connected pipes",
+					index + 1,
+					first_tile.x,
+					first_tile.y,
+					component.tiles.len()
+				),
+			}
 		}
+		let loop_count = components
+			.iter()
+			.filter(|component| component.closed_loop.is_some())
+			.count();
+		println!("{} closed loops", loop_count);
+	}
+
+	let analysis = analysis?;
 
-		current_distance += 1;
-		for tile in next_tiles.iter() {
-			pipe_distances.insert(*tile, current_distance);
+	if show_summary {
+		println!("S is a {} pipe", analysis.start_shape);
+		println!("Loop length: {}", analysis.loop_tiles.len());
+		println!("Farthest distance: {}", analysis.farthest_distance());
+	}
+	if show_render {
+		print!("{}", render(&pipe_tiles, &analysis, width, height));
+	}
+	if show_interior {
+		for tile in analysis.interior_tiles.iter() {
+			println!("Inside: ({}, {})", tile.x, tile.y);
 		}
-		current_tiles = next_tiles;
 	}
 
-	println!("{}", current_distance);
+	if count_enclosed {
+		println!("{}", analysis.enclosed_count());
+	} else {
+		println!("{}", analysis.farthest_distance());
+	}
 
 	Ok(())
 }
@@ -1,58 +1,253 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::env;
 use std::error::Error;
 use std::fs;
 
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 struct Coordinate {
-	x: u32,
-	y: u32,
+	x: u64,
+	y: u64,
+}
+
+/// How far each row and column of the image stretches. Empty rows and columns take their axis's expansion factor,
+/// others take 1, and any row or column can be given its own weight instead.
+struct Expansion {
+	row_factor: u64,
+	column_factor: u64,
+	row_weights: HashMap<u64, u64>,
+	column_weights: HashMap<u64, u64>,
+}
+
+impl Expansion {
+	fn uniform(factor: u64) -> Self {
+		Self {
+			row_factor: factor,
+			column_factor: factor,
+			row_weights: HashMap::new(),
+			column_weights: HashMap::new(),
+		}
+	}
+}
+
+/// Gets the expanded position of the start of each line along one axis as a prefix sum of line weights.
+fn expanded_positions(occupied: &[bool], factor: u64, weights: &HashMap<u64, u64>) -> Vec<u64> {
+	let mut positions: Vec<u64> = Vec::with_capacity(occupied.len());
+	let mut position = 0;
+	for (line, line_occupied) in occupied.iter().enumerate() {
+		positions.push(position);
+		let default_weight = if *line_occupied { 1 } else { factor };
+		position += weights.get(&(line as u64)).copied().unwrap_or(default_weight);
+	}
+	positions
+}
+
+/// The galaxies as parsed from the image, which can be expanded by any amount without re-reading them.
+struct Universe {
+	galaxies: Vec<Coordinate>,
+	occupied_rows: Vec<bool>,
+	occupied_columns: Vec<bool>,
+}
+
+impl Universe {
+	fn new(galaxies: Vec<Coordinate>) -> Self {
+		let max_x = galaxies.iter().map(|coord| coord.x).max().unwrap_or(0);
+		let max_y = galaxies.iter().map(|coord| coord.y).max().unwrap_or(0);
+		let mut occupied_rows = vec![false; max_y as usize + 1];
+		let mut occupied_columns = vec![false; max_x as usize + 1];
+		for coord in galaxies.iter() {
+			occupied_rows[coord.y as usize] = true;
+			occupied_columns[coord.x as usize] = true;
+		}
+		Self {
+			galaxies,
+			occupied_rows,
+			occupied_columns,
+		}
+	}
+
+	fn expanded(&self, expansion: &Expansion) -> Vec<Coordinate> {
+		let row_positions = expanded_positions(&self.occupied_rows, expansion.row_factor, &expansion.row_weights);
+		let column_positions = expanded_positions(
+			&self.occupied_columns,
+			expansion.column_factor,
+			&expansion.column_weights,
+		);
+		self.galaxies
+			.iter()
+			.map(|coord| Coordinate {
+				x: column_positions[coord.x as usize],
+				y: row_positions[coord.y as usize],
+			})
+			.collect()
+	}
+}
+
+fn distance(lhs: &Coordinate, rhs: &Coordinate) -> u64 {
+	lhs.x.abs_diff(rhs.x) + lhs.y.abs_diff(rhs.y)
+}
+
+/// Gets the `count` galaxies closest to the galaxy at `index`, nearest first, as indices paired with distances.
+fn nearest_galaxies(galaxies: &[Coordinate], index: usize, count: usize) -> Vec<(usize, u64)> {
+	let galaxy = &galaxies[index];
+	let mut distances: Vec<(usize, u64)> = galaxies
+		.iter()
+		.enumerate()
+		.filter(|(other_index, _)| *other_index != index)
+		.map(|(other_index, other)| (other_index, distance(galaxy, other)))
+		.collect();
+	distances.sort_unstable_by_key(|(other_index, distance)| (*distance, *other_index));
+	distances.truncate(count);
+	distances
+}
+
+/// Counts pairs of galaxies by distance, grouped into buckets of the given width.
+fn distance_histogram(galaxies: &[Coordinate], bucket_width: u64) -> Vec<(u64, usize)> {
+	let mut buckets: HashMap<u64, usize> = HashMap::new();
+	for (first_index, first) in galaxies.iter().enumerate() {
+		for second in galaxies.iter().skip(first_index + 1) {
+			*buckets.entry(distance(first, second) / bucket_width).or_default() += 1;
+		}
+	}
+	let mut buckets: Vec<(u64, usize)> = buckets.into_iter().collect();
+	buckets.sort_unstable();
+	buckets
+}
+
+/// Sums the distance between every pair of positions on one axis by sorting them and using a running prefix sum.
+fn pairwise_axis_distance(mut positions: Vec<u64>) -> u128 {
+	positions.sort_unstable();
+	let mut total: u128 = 0;
+	let mut prefix_sum: u128 = 0;
+	for (index, position) in positions.iter().enumerate() {
+		total += *position as u128 * index as u128 - prefix_sum;
+		prefix_sum += *position as u128;
+	}
+	total
+}
+
+fn total_pairwise_distance(galaxies: &[Coordinate]) -> u128 {
+	pairwise_axis_distance(galaxies.iter().map(|coord| coord.x).collect())
+		+ pairwise_axis_distance(galaxies.iter().map(|coord| coord.y).collect())
+}
+
+fn parse_weight(description: &str) -> Result<(u64, u64), Box<dyn Error>> {
+	let Some((line, weight)) = description.split_once('=') else {
+		return Err(format!("Weights must be given as line=weight: {}", description).into());
+	};
+	Ok((line.parse()?, weight.parse()?))
+}
+
+fn parse_galaxy_number(arg: Option<String>, flag: &str) -> Result<usize, Box<dyn Error>> {
+	let number: usize = arg.ok_or(format!("{} requires a galaxy number", flag))?.parse()?;
+	if number == 0 {
+		return Err("Galaxies are numbered from 1".into());
+	}
+	Ok(number - 1)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-	let galaxies = {
+	let mut expansion = Expansion::uniform(2);
+	let mut distance_query: Option<(usize, usize)> = None;
+	let mut nearest_query: Option<(usize, usize)> = None;
+	let mut histogram_bucket_width: Option<u64> = None;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			// Part two of the puzzle is `--expansion 1000000`
+			"--expansion" => {
+				let factor = args.next().ok_or("--expansion requires a value")?.parse()?;
+				expansion.row_factor = factor;
+				expansion.column_factor = factor;
+			}
+			"--row-expansion" => {
+				expansion.row_factor = args.next().ok_or("--row-expansion requires a value")?.parse()?
+			}
+			"--column-expansion" => {
+				expansion.column_factor = args.next().ok_or("--column-expansion requires a value")?.parse()?
+			}
+			"--row-weight" => {
+				let (row, weight) = parse_weight(&args.next().ok_or("--row-weight requires a value")?)?;
+				expansion.row_weights.insert(row, weight);
+			}
+			"--column-weight" => {
+				let (column, weight) = parse_weight(&args.next().ok_or("--column-weight requires a value")?)?;
+				expansion.column_weights.insert(column, weight);
+			}
+			"--distance" => {
+				let first = parse_galaxy_number(args.next(), "--distance")?;
+				let second = parse_galaxy_number(args.next(), "--distance")?;
+				distance_query = Some((first, second));
+			}
+			"--nearest" => {
+				let galaxy = parse_galaxy_number(args.next(), "--nearest")?;
+				let count = args.next().ok_or("--nearest requires a count")?.parse()?;
+				nearest_query = Some((galaxy, count));
+			}
+			"--histogram" => {
+				let bucket_width: u64 = args.next().ok_or("--histogram requires a bucket width")?.parse()?;
+				if bucket_width == 0 {
+					return Err("Histogram buckets must be at least 1 wide".into());
+				}
+				histogram_bucket_width = Some(bucket_width);
+			}
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
+	let universe = {
 		let input = fs::read_to_string("input.txt")?;
 
 		let mut galaxy_coordinates: Vec<Coordinate> = Vec::new();
 		for (y, line) in input.lines().enumerate() {
 			for (x, c) in line.chars().enumerate() {
 				if c == '#' {
-					galaxy_coordinates.push(Coordinate { x: x as u32, y: y as u32 });
+					galaxy_coordinates.push(Coordinate {
+						x: x as u64,
+						y: y as u64,
+					});
 				}
 			}
 		}
 
-		let mut max_x = 0;
-		let mut max_y = 0;
-		for coord in galaxy_coordinates.iter() {
-			max_x = max_x.max(coord.x);
-			max_y = max_y.max(coord.y);
-		}
-		let mut empty_rows: HashSet<u32> = (0..=max_y).collect();
-		let mut empty_columns: HashSet<u32> = (0..=max_x).collect();
-		for coord in galaxy_coordinates.iter() {
-			empty_rows.remove(&coord.y);
-			empty_columns.remove(&coord.x);
-		}
-
-		for coord in galaxy_coordinates.iter_mut() {
-			let add_x = empty_columns.iter().filter(|col| **col < coord.x).count() as u32;
-			let add_y = empty_rows.iter().filter(|col| **col < coord.y).count() as u32;
+		Universe::new(galaxy_coordinates)
+	};
 
-			coord.x += add_x;
-			coord.y += add_y;
+	let expanded_galaxies = universe.expanded(&expansion);
+	let galaxy_count = expanded_galaxies.len();
+	for galaxy in [
+		distance_query.map(|(first, _)| first),
+		distance_query.map(|(_, second)| second),
+		nearest_query.map(|(galaxy, _)| galaxy),
+	]
+	.into_iter()
+	.flatten()
+	{
+		if galaxy >= galaxy_count {
+			return Err(format!("There is no galaxy {}; the image has {}", galaxy + 1, galaxy_count).into());
 		}
+	}
 
-		galaxy_coordinates
-	};
-
-	let mut distance = 0;
-	for (first_index, first) in galaxies.iter().enumerate() {
-		for second in galaxies.iter().skip(first_index + 1) {
-			distance += first.x.abs_diff(second.x) + first.y.abs_diff(second.y);
+	if let Some((first, second)) = distance_query {
+		println!(
+			"Galaxy {} to galaxy {}: {}",
+			first + 1,
+			second + 1,
+			distance(&expanded_galaxies[first], &expanded_galaxies[second])
+		);
+	}
+	if let Some((galaxy, count)) = nearest_query {
+		for (other, other_distance) in nearest_galaxies(&expanded_galaxies, galaxy, count) {
+			println!("Galaxy {} is {} from galaxy {}", other + 1, other_distance, galaxy + 1);
+		}
+	}
+	if let Some(bucket_width) = histogram_bucket_width {
+		for (bucket, pair_count) in distance_histogram(&expanded_galaxies, bucket_width) {
+			let low = bucket * bucket_width;
+			println!("{}-{}: {}", low, low + bucket_width - 1, pair_count);
 		}
 	}
 
-	println!("{}", distance);
+	println!("{}", total_pairwise_distance(&expanded_galaxies));
 
 	Ok(())
 }
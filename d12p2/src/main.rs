@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::env;
 use std::error::Error;
 use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 enum SpringStatus {
@@ -19,87 +20,423 @@ impl SpringStatus {
 	}
 }
 
-fn place_springs(
-	remaining_springs: &[SpringStatus],
-	remaining_arrangements: &[u64],
-	cache: &mut HashMap<(Vec<SpringStatus>, Vec<u64>), u64>,
-) -> u64 {
-	let Some(first_arrangement) = remaining_arrangements.first().copied() else {
-		if remaining_springs
-			.iter()
-			.any(|spring| *spring == SpringStatus::Operational)
-		{
-			return 0;
+/// Counts arrangements with a flat table indexed by spring position and group index. Each entry holds the number
+/// of ways to place the groups from that index onwards in the springs from that position onwards, saturating at
+/// `u128::MAX`.
+struct ArrangementTable<'a> {
+	springs: &'a [SpringStatus],
+	groups: &'a [u64],
+	damaged_prefix: Vec<usize>,
+	ways: Vec<u128>,
+}
+
+impl<'a> ArrangementTable<'a> {
+	fn new(springs: &'a [SpringStatus], groups: &'a [u64]) -> Self {
+		let mut damaged_prefix: Vec<usize> = vec![0];
+		for spring in springs.iter() {
+			let damaged = usize::from(*spring == SpringStatus::Damaged);
+			damaged_prefix.push(damaged_prefix.last().unwrap() + damaged);
 		}
-		return 1;
-	};
 
-	let remaining_arrangements_total: usize =
-		(remaining_arrangements.iter().copied().sum::<u64>() as usize) + remaining_arrangements.len() - 1;
+		let mut table = Self {
+			springs,
+			groups,
+			damaged_prefix,
+			ways: vec![0; (springs.len() + 1) * (groups.len() + 1)],
+		};
+		let end_index = table.index(springs.len(), groups.len());
+		table.ways[end_index] = 1;
+		for position in (0..springs.len()).rev() {
+			for group in (0..=groups.len()).rev() {
+				let mut ways = 0;
+				if springs[position] != SpringStatus::Operational {
+					ways = table.ways(position + 1, group);
+				}
+				if table.group_fits(position, group) {
+					let next_position = (position + groups[group] as usize + 1).min(springs.len());
+					ways = ways.saturating_add(table.ways(next_position, group + 1));
+				}
+				let index = table.index(position, group);
+				table.ways[index] = ways;
+			}
+		}
+		table
+	}
 
-	if remaining_arrangements_total > remaining_springs.len() {
-		return 0;
+	fn index(&self, position: usize, group: usize) -> usize {
+		position * (self.groups.len() + 1) + group
 	}
 
-	if (first_arrangement as usize) == remaining_springs.len() {
-		if remaining_springs.iter().all(|spring| *spring != SpringStatus::Damaged) && remaining_arrangements.len() == 1
-		{
-			return 1;
-		}
-		return 0;
+	fn ways(&self, position: usize, group: usize) -> u128 {
+		self.ways[self.index(position, group)]
+	}
+
+	fn count(&self) -> u128 {
+		self.ways(0, 0)
+	}
+
+	/// Checks whether the given group can start at the given position, including the gap after it.
+	fn group_fits(&self, position: usize, group: usize) -> bool {
+		let Some(length) = self.groups.get(group).map(|length| *length as usize) else {
+			return false;
+		};
+		let end = position + length;
+		end <= self.springs.len()
+			&& self.damaged_prefix[end] == self.damaged_prefix[position]
+			&& self.springs.get(end) != Some(&SpringStatus::Operational)
 	}
 
-	let mut arrangements = 0;
-	for start in 0..(remaining_springs.len() - remaining_arrangements_total + 1) {
-		if !remaining_springs
+	/// Solves as much of the row as every arrangement agrees on, leaving the rest unknown, or gives `None` if there are
+	/// no arrangements at all. States reachable from the start are walked forwards and only kept where the table says
+	/// they can still complete.
+	fn settled(&self) -> Option<Vec<SpringStatus>> {
+		if self.count() == 0 {
+			return None;
+		}
+
+		let mut reachable: Vec<bool> = vec![false; self.ways.len()];
+		reachable[0] = true;
+		let mut can_be_damaged: Vec<bool> = vec![false; self.springs.len()];
+		let mut operational_difference: Vec<i64> = vec![0; self.springs.len() + 1];
+		for position in 0..self.springs.len() {
+			for group in 0..=self.groups.len() {
+				if !reachable[self.index(position, group)] {
+					continue;
+				}
+				if self.springs[position] != SpringStatus::Operational && self.ways(position + 1, group) > 0 {
+					can_be_damaged[position] = true;
+					let index = self.index(position + 1, group);
+					reachable[index] = true;
+				}
+				if self.group_fits(position, group) {
+					let (next_position, placed) = self.place_group(position, group);
+					if self.ways(next_position, group + 1) > 0 {
+						let end = position + self.groups[group] as usize;
+						operational_difference[position] += 1;
+						operational_difference[end] -= 1;
+						if placed.len() > end - position {
+							can_be_damaged[end] = true;
+						}
+						let index = self.index(next_position, group + 1);
+						reachable[index] = true;
+					}
+				}
+			}
+		}
+
+		let mut operational_count = 0;
+		let settled = can_be_damaged
 			.iter()
-			.skip(start)
-			.take(first_arrangement as usize)
-			.any(|spring| *spring == SpringStatus::Damaged)
-			&& remaining_springs.get(start + first_arrangement as usize).copied() != Some(SpringStatus::Operational)
-		{
-			let next_start_index = start + first_arrangement as usize + 1;
-			if next_start_index >= remaining_springs.len() {
-				if remaining_arrangements.len() == 1 {
-					arrangements += 1;
+			.zip(operational_difference.iter())
+			.map(|(can_be_damaged, difference)| {
+				operational_count += difference;
+				match (operational_count > 0, *can_be_damaged) {
+					(true, false) => SpringStatus::Operational,
+					(false, true) => SpringStatus::Damaged,
+					_ => SpringStatus::Unknown,
+				}
+			})
+			.collect();
+		Some(settled)
+	}
+}
+
+/// Lazily walks every concrete arrangement of a row in order, only following branches the table says can complete.
+struct Arrangements<'t, 'a> {
+	table: &'t ArrangementTable<'a>,
+	stack: Vec<(usize, usize, u8)>,
+	current: Vec<SpringStatus>,
+}
+
+impl<'t, 'a> Arrangements<'t, 'a> {
+	fn new(table: &'t ArrangementTable<'a>) -> Self {
+		let stack = if table.count() > 0 { vec![(0, 0, 0)] } else { Vec::new() };
+		Self {
+			table,
+			stack,
+			current: Vec::new(),
+		}
+	}
+}
+
+impl Iterator for Arrangements<'_, '_> {
+	type Item = Vec<SpringStatus>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let springs = self.table.springs;
+		loop {
+			let (position, group, next_option) = self.stack.last_mut()?;
+			let (position, group) = (*position, *group);
+			self.current.truncate(position);
+			if position == springs.len() {
+				self.stack.pop();
+				return Some(self.current.clone());
+			}
+
+			match *next_option {
+				0 => {
+					*next_option = 1;
+					if springs[position] != SpringStatus::Operational && self.table.ways(position + 1, group) > 0 {
+						self.current.push(SpringStatus::Damaged);
+						self.stack.push((position + 1, group, 0));
+					}
+				}
+				1 => {
+					*next_option = 2;
+					if self.table.group_fits(position, group) {
+						let (next_position, placed) = self.table.place_group(position, group);
+						if self.table.ways(next_position, group + 1) > 0 {
+							self.current.extend(placed);
+							self.stack.push((next_position, group + 1, 0));
+						}
+					}
 				}
+				_ => {
+					self.stack.pop();
+				}
+			}
+		}
+	}
+}
+
+/// A small SplitMix64 generator, so sampling doesn't need an external crate.
+struct Random {
+	state: u64,
+}
+
+impl Random {
+	fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+		let mut value = self.state;
+		value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+		value ^ (value >> 31)
+	}
+
+	/// Gets a uniformly random value below `bound` by rejecting values from the incomplete final block.
+	fn below(&mut self, bound: u128) -> u128 {
+		let zone = u128::MAX - u128::MAX % bound;
+		loop {
+			let value = ((self.next_u64() as u128) << 64) | self.next_u64() as u128;
+			if value < zone {
+				return value % bound;
+			}
+		}
+	}
+}
+
+impl ArrangementTable<'_> {
+	/// Gets the springs for the given group placed at the given position, with the gap after it, and where the next
+	/// group could start.
+	fn place_group(&self, position: usize, group: usize) -> (usize, Vec<SpringStatus>) {
+		let end = position + self.groups[group] as usize;
+		let mut placed = vec![SpringStatus::Operational; end - position];
+		if end < self.springs.len() {
+			placed.push(SpringStatus::Damaged);
+		}
+		(position + placed.len(), placed)
+	}
+
+	/// Draws one arrangement uniformly at random by choosing each branch in proportion to how many arrangements
+	/// complete it.
+	fn sample(&self, random: &mut Random) -> Result<Vec<SpringStatus>, Box<dyn Error>> {
+		if self.count() == u128::MAX {
+			return Err("Too many arrangements to sample uniformly".into());
+		}
+		if self.count() == 0 {
+			return Err("Row has no valid arrangements".into());
+		}
+
+		let mut arrangement: Vec<SpringStatus> = Vec::new();
+		let mut position = 0;
+		let mut group = 0;
+		while position < self.springs.len() {
+			let gap_ways = if self.springs[position] != SpringStatus::Operational {
+				self.ways(position + 1, group)
+			} else {
+				0
+			};
+			let group_ways = if self.group_fits(position, group) {
+				self.ways(self.place_group(position, group).0, group + 1)
 			} else {
-				let cache_key = (
-					remaining_springs[next_start_index..].to_vec(),
-					remaining_arrangements[1..].to_vec(),
-				);
-				if let Some(cache_result) = cache.get(&cache_key) {
-					arrangements += *cache_result;
-				} else {
-					let sub_arrangements = place_springs(
-						&remaining_springs[next_start_index..],
-						&remaining_arrangements[1..],
-						cache,
-					);
-					cache.insert(cache_key, sub_arrangements);
-					arrangements += sub_arrangements;
+				0
+			};
+
+			if random.below(gap_ways + group_ways) < gap_ways {
+				arrangement.push(SpringStatus::Damaged);
+				position += 1;
+			} else {
+				let (next_position, placed) = self.place_group(position, group);
+				arrangement.extend(placed);
+				position = next_position;
+				group += 1;
+			}
+		}
+		Ok(arrangement)
+	}
+}
+
+/// A 2D puzzle where every row and column is a line of springs with its own groups, filled in with `#` and `.`.
+struct Nonogram {
+	row_clues: Vec<Vec<u64>>,
+	column_clues: Vec<Vec<u64>>,
+}
+
+enum Solutions {
+	None,
+	Unique(Vec<Vec<SpringStatus>>),
+	Many(Vec<Vec<SpringStatus>>, Vec<Vec<SpringStatus>>),
+}
+
+impl Nonogram {
+	/// Parses row clues then column clues, as two blocks separated by a blank line, with one line of `,`-separated
+	/// group sizes per row or column. A line with no groups is written as `0`.
+	fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+		let mut blocks: Vec<Vec<Vec<u64>>> = vec![Vec::new()];
+		for line in input.lines().map(|line| line.trim()) {
+			if line.is_empty() {
+				if !blocks.last().unwrap().is_empty() {
+					blocks.push(Vec::new());
+				}
+				continue;
+			}
+			let clue: Result<Vec<u64>, _> = line.split(',').map(|group| group.trim().parse()).collect();
+			let clue: Vec<u64> = clue?.into_iter().filter(|group| *group > 0).collect();
+			blocks.last_mut().unwrap().push(clue);
+		}
+		if blocks.last().is_some_and(|block| block.is_empty()) {
+			blocks.pop();
+		}
+
+		let Ok([row_clues, column_clues]) = <[Vec<Vec<u64>>; 2]>::try_from(blocks) else {
+			return Err("Nonograms need a block of row clues and a block of column clues".into());
+		};
+		Ok(Self {
+			row_clues,
+			column_clues,
+		})
+	}
+
+	/// Settles every row and column in turn until nothing changes, giving `false` if any line has no arrangements.
+	fn propagate(&self, grid: &mut [Vec<SpringStatus>]) -> bool {
+		let mut changed = true;
+		while changed {
+			changed = false;
+			for (row, clue) in grid.iter_mut().zip(self.row_clues.iter()) {
+				let Some(settled) = ArrangementTable::new(row, clue).settled() else {
+					return false;
+				};
+				if *row != settled {
+					*row = settled;
+					changed = true;
+				}
+			}
+			for (column, clue) in self.column_clues.iter().enumerate() {
+				let line: Vec<SpringStatus> = grid.iter().map(|row| row[column]).collect();
+				let Some(settled) = ArrangementTable::new(&line, clue).settled() else {
+					return false;
+				};
+				if line != settled {
+					for (row, spring) in grid.iter_mut().zip(settled) {
+						row[column] = spring;
+					}
+					changed = true;
 				}
 			}
 		}
+		true
+	}
+
+	/// Collects solutions until `limit` are found, guessing the first unknown cell whenever propagation gets stuck.
+	fn search(&self, mut grid: Vec<Vec<SpringStatus>>, limit: usize, solutions: &mut Vec<Vec<Vec<SpringStatus>>>) {
+		if solutions.len() >= limit || !self.propagate(&mut grid) {
+			return;
+		}
 
-		if *remaining_springs.get(start).unwrap() == SpringStatus::Operational {
-			// Moving past a guaranteed working one means we no longer start in the right spot
-			break;
+		let unknown = grid.iter().enumerate().find_map(|(y, row)| {
+			row.iter()
+				.position(|spring| *spring == SpringStatus::Unknown)
+				.map(|x| (x, y))
+		});
+		let Some((x, y)) = unknown else {
+			solutions.push(grid);
+			return;
+		};
+		for guess in [SpringStatus::Operational, SpringStatus::Damaged] {
+			let mut guessed_grid = grid.clone();
+			guessed_grid[y][x] = guess;
+			self.search(guessed_grid, limit, solutions);
 		}
 	}
 
-	arrangements
+	fn solve(&self) -> Solutions {
+		let grid = vec![vec![SpringStatus::Unknown; self.column_clues.len()]; self.row_clues.len()];
+		let mut solutions: Vec<Vec<Vec<SpringStatus>>> = Vec::new();
+		self.search(grid, 2, &mut solutions);
+
+		let mut solutions = solutions.into_iter();
+		match (solutions.next(), solutions.next()) {
+			(None, _) => Solutions::None,
+			(Some(solution), None) => Solutions::Unique(solution),
+			(Some(first), Some(second)) => Solutions::Many(first, second),
+		}
+	}
 }
 
-fn count_sets(spring_set: &[SpringStatus], arrangements: &[u64]) -> u64 {
-	let mut cache: HashMap<(Vec<SpringStatus>, Vec<u64>), u64> = HashMap::new();
-	let value = place_springs(spring_set, arrangements, &mut cache);
+fn print_grid(grid: &[Vec<SpringStatus>]) {
+	for row in grid.iter() {
+		let row_output: String = row.iter().map(|spring| spring.char_rep()).collect();
+		println!("{}", row_output);
+	}
+}
+
+fn count_sets(spring_set: &[SpringStatus], arrangements: &[u64]) -> u128 {
+	let value = ArrangementTable::new(spring_set, arrangements).count();
 	let spring_set_output: String = spring_set.iter().map(|spring| spring.char_rep()).collect();
 	println!("{}: {}", value, spring_set_output);
 	value
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+	let mut unfold_factor = 5;
+	let mut list_limit: Option<usize> = None;
+	let mut sample_count: Option<usize> = None;
+	let mut seed: Option<u64> = None;
+	let mut nonogram_path: Option<String> = None;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--nonogram" => nonogram_path = Some(args.next().ok_or("--nonogram requires a path")?),
+			"--list" => list_limit = Some(usize::MAX),
+			"--list-limit" => list_limit = Some(args.next().ok_or("--list-limit requires a value")?.parse()?),
+			"--sample" => sample_count = Some(args.next().ok_or("--sample requires a value")?.parse()?),
+			"--seed" => seed = Some(args.next().ok_or("--seed requires a value")?.parse()?),
+			"--unfold" => unfold_factor = args.next().ok_or("--unfold requires a value")?.parse()?,
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+	if unfold_factor == 0 {
+		return Err("Rows must be unfolded at least once".into());
+	}
+
+	if let Some(nonogram_path) = nonogram_path {
+		let nonogram = Nonogram::parse(&fs::read_to_string(nonogram_path)?)?;
+		match nonogram.solve() {
+			Solutions::None => println!("No solutions"),
+			Solutions::Unique(solution) => {
+				println!("Unique solution:");
+				print_grid(&solution);
+			}
+			Solutions::Many(first, second) => {
+				println!("Many solutions, including:");
+				print_grid(&first);
+				println!();
+				print_grid(&second);
+			}
+		}
+		return Ok(());
+	}
+
 	let spring_sets: Vec<(Vec<SpringStatus>, Vec<u64>)> = {
 		let input = fs::read_to_string("input.txt")?;
 
@@ -113,7 +450,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 			let mut full_spring_set: String = String::new();
 			let mut full_arrangements: Vec<u64> = Vec::new();
-			for _ in 0..5 {
+			for _ in 0..unfold_factor {
 				full_spring_set.push_str(&spring_set);
 				full_spring_set.push('?');
 				for arrangement in arrangements.iter().copied() {
@@ -136,10 +473,40 @@ fn main() -> Result<(), Box<dyn Error>> {
 		spring_sets
 	};
 
-	let possible_arrangements: u64 = spring_sets
+	if list_limit.is_some() || sample_count.is_some() {
+		let seed = match seed {
+			Some(seed) => seed,
+			None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+		};
+		let mut random = Random { state: seed };
+		for (set, arrangements) in spring_sets.iter() {
+			let table = ArrangementTable::new(set, arrangements);
+			let spring_set_output: String = set.iter().map(|spring| spring.char_rep()).collect();
+			println!("{}: {}", table.count(), spring_set_output);
+			if let Some(limit) = list_limit {
+				for arrangement in Arrangements::new(&table).take(limit) {
+					let arrangement_output: String = arrangement.iter().map(|spring| spring.char_rep()).collect();
+					println!("  {}", arrangement_output);
+				}
+			}
+			if let Some(count) = sample_count {
+				for _ in 0..count {
+					let arrangement = table.sample(&mut random)?;
+					let arrangement_output: String = arrangement.iter().map(|spring| spring.char_rep()).collect();
+					println!("  random: {}", arrangement_output);
+				}
+			}
+		}
+		return Ok(());
+	}
+
+	let possible_arrangements = spring_sets
 		.iter()
 		.map(|(set, arrangements)| count_sets(set, arrangements))
-		.sum();
+		.fold(0u128, |total, count| total.saturating_add(count));
+	if possible_arrangements == u128::MAX {
+		return Err("Too many arrangements to count".into());
+	}
 
 	println!("{}", possible_arrangements);
 
@@ -1,197 +1,243 @@
-use std::collections::HashMap;
+use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs;
 
 #[derive(Clone, Copy, Eq, PartialEq)]
-enum DifferenceType {
-	Same,
-	Single,
-	Multiple,
+struct Coordinate {
+	x: usize,
+	y: usize,
 }
 
-fn find_differences(lhs: &[usize], rhs: &[usize]) -> DifferenceType {
-	if *lhs == *rhs {
-		return DifferenceType::Same;
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Axis {
+	Vertical,
+	Horizontal,
+}
+
+/// A line of reflection, between `before` and `before + 1` columns (for a vertical line) or rows (for a horizontal
+/// one). Each smudge is a cell paired with its mirror image, which it would match if it were flipped.
+struct Reflection {
+	axis: Axis,
+	before: usize,
+	smudges: Vec<(Coordinate, Coordinate)>,
+}
+
+impl Reflection {
+	fn summary(&self) -> usize {
+		match self.axis {
+			Axis::Vertical => self.before,
+			Axis::Horizontal => self.before * 100,
+		}
 	}
+}
 
-	let mut lhs_iter = lhs.iter();
-	let mut rhs_iter = rhs.iter();
-	loop {
-		let lhs_val = lhs_iter.next();
-		let rhs_val = rhs_iter.next();
-		let Some(lhs_val) = lhs_val else {
-			match rhs_val {
-				Some(_) => {
-					if rhs_iter.next().is_some() {
-						return DifferenceType::Multiple;
-					}
-					return DifferenceType::Single;
-				}
-				None => {
-					return DifferenceType::Same;
+impl fmt::Display for Reflection {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.axis {
+			Axis::Vertical => write!(f, "vertical line after column {}", self.before)?,
+			Axis::Horizontal => write!(f, "horizontal line after row {}", self.before)?,
+		}
+		for (smudge, mirror) in self.smudges.iter() {
+			write!(
+				f,
+				", smudge at column {} row {} (mirroring column {} row {})",
+				smudge.x + 1,
+				smudge.y + 1,
+				mirror.x + 1,
+				mirror.y + 1
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// Finds every line between two of the given lines where the mirrored lines differ in exactly `smudges` cells in total.
+/// Each is given with its differences, as the index of the line before the reflection and the differing bit.
+fn find_reflections(lines: &[u64], smudges: u32) -> Vec<(usize, Vec<(usize, u32)>)> {
+	(1..lines.len())
+		.filter_map(|before| {
+			let mut differences = 0;
+			for (low, high) in lines[..before].iter().rev().zip(lines[before..].iter()) {
+				differences += (low ^ high).count_ones();
+				if differences > smudges {
+					return None;
 				}
 			}
-		};
-		let Some(rhs_val) = rhs_val else {
-			if lhs_iter.next().is_some() {
-				return DifferenceType::Multiple;
+			if differences != smudges {
+				return None;
 			}
-			return DifferenceType::Single;
-		};
-
-		if *lhs_val != *rhs_val {
-			let mut new_lhs: Vec<usize> = lhs_iter.copied().collect();
-			let mut new_rhs: Vec<usize> = rhs_iter.copied().collect();
-			new_lhs.insert(0, *lhs_val);
-			new_rhs.insert(0, *rhs_val);
-
-			let advance_lhs_differences = find_differences(&new_lhs[1..], &new_rhs);
-			let advance_rhs_differences = find_differences(&new_lhs, &new_rhs[1..]);
 
-			if advance_lhs_differences == DifferenceType::Same || advance_rhs_differences == DifferenceType::Same {
-				break DifferenceType::Single;
+			let mut differences: Vec<(usize, u32)> = Vec::new();
+			for ((line, low), high) in lines[..before].iter().enumerate().rev().zip(lines[before..].iter()) {
+				let mut mismatches = low ^ high;
+				while mismatches != 0 {
+					differences.push((line, mismatches.trailing_zeros()));
+					mismatches &= mismatches - 1;
+				}
 			}
-			break DifferenceType::Multiple;
-		}
-	}
+			Some((before, differences))
+		})
+		.collect()
 }
 
+/// A pattern with each row and each column packed into a bitmask, with bit `n` set where cell `n` along it is `#`.
 struct Grid {
-	coords_by_x: HashMap<usize, Vec<usize>>,
-	coords_by_y: HashMap<usize, Vec<usize>>,
-	max_x: usize,
-	max_y: usize,
+	rows: Vec<u64>,
+	columns: Vec<u64>,
 }
 
 impl Grid {
-	fn new() -> Self {
-		let coords_by_x = HashMap::new();
-		let coords_by_y = HashMap::new();
-		let max_x = 0;
-		let max_y = 0;
-		Self {
-			coords_by_x,
-			coords_by_y,
-			max_x,
-			max_y,
+	fn parse(pattern: &[&str]) -> Result<Self, Box<dyn Error>> {
+		let width = pattern.first().map(|line| line.len()).unwrap_or(0);
+		if width > u64::BITS as usize || pattern.len() > u64::BITS as usize {
+			return Err(format!("Patterns can be at most {} cells across", u64::BITS).into());
 		}
-	}
-
-	fn add_coordinate(&mut self, x: usize, y: usize) {
-		let x_y_coords = self.coords_by_x.entry(x).or_default();
-		x_y_coords.push(y);
-		x_y_coords.sort_unstable();
-
-		let y_x_coords = self.coords_by_y.entry(y).or_default();
-		y_x_coords.push(x);
-		y_x_coords.sort_unstable();
 
-		self.max_x = self.max_x.max(x);
-		self.max_y = self.max_y.max(y);
-	}
-
-	/// Finds a reflection over a line on a single x coordinate. Returns the number of columns to the left.
-	fn find_x_reflection(&self) -> Option<usize> {
-		'x: for x in 1..=self.max_x {
-			let mut found_single_difference = false;
-			for low_x in (0..x).rev() {
-				let high_x = x + (x - low_x) - 1;
-				if high_x > self.max_x {
-					break;
-				}
-				let low_x_coords = self.coords_by_x.get(&low_x).cloned().unwrap_or_default();
-				let high_x_coords = self.coords_by_x.get(&high_x).cloned().unwrap_or_default();
-				match find_differences(&low_x_coords, &high_x_coords) {
-					DifferenceType::Same => (),
-					DifferenceType::Single => {
-						if found_single_difference {
-							continue 'x;
-						}
-						found_single_difference = true;
+		let mut rows: Vec<u64> = Vec::with_capacity(pattern.len());
+		let mut columns: Vec<u64> = vec![0; width];
+		for (y, line) in pattern.iter().enumerate() {
+			if line.len() != width {
+				return Err(format!("Pattern rows have different lengths: {}", line).into());
+			}
+			let mut row = 0;
+			for (x, c) in line.chars().enumerate() {
+				match c {
+					'#' => {
+						row |= 1 << x;
+						columns[x] |= 1 << y;
 					}
-					DifferenceType::Multiple => continue 'x,
+					'.' => (),
+					_ => return Err(format!("Unknown pattern cell: {}", c).into()),
 				}
 			}
-			if found_single_difference {
-				return Some(x);
-			}
+			rows.push(row);
 		}
-		None
+		Ok(Self { rows, columns })
 	}
 
-	/// Finds a reflection over a line on a single y coordinate. Returns the number of rows above.
-	fn find_y_reflection(&self) -> Option<usize> {
-		'y: for y in 1..=self.max_y {
-			let mut found_single_difference = false;
-			for low_y in (0..y).rev() {
-				let high_y = y + (y - low_y) - 1;
-				if high_y > self.max_y {
-					break;
-				}
-				let low_y_coords = self.coords_by_y.get(&low_y).cloned().unwrap_or_default();
-				let high_y_coords = self.coords_by_y.get(&high_y).cloned().unwrap_or_default();
-				match find_differences(&low_y_coords, &high_y_coords) {
-					DifferenceType::Same => (),
-					DifferenceType::Single => {
-						if found_single_difference {
-							continue 'y;
-						}
-						found_single_difference = true;
-					}
-					DifferenceType::Multiple => continue 'y,
-				}
+	fn reflections(&self, smudges: u32) -> Vec<Reflection> {
+		let vertical = find_reflections(&self.columns, smudges)
+			.into_iter()
+			.map(|(before, differences)| Reflection {
+				axis: Axis::Vertical,
+				before,
+				smudges: differences
+					.into_iter()
+					.map(|(x, y)| {
+						let smudge = Coordinate { x, y: y as usize };
+						let mirror = Coordinate {
+							x: 2 * before - 1 - x,
+							y: y as usize,
+						};
+						(smudge, mirror)
+					})
+					.collect(),
+			});
+		let horizontal = find_reflections(&self.rows, smudges)
+			.into_iter()
+			.map(|(before, differences)| Reflection {
+				axis: Axis::Horizontal,
+				before,
+				smudges: differences
+					.into_iter()
+					.map(|(y, x)| {
+						let smudge = Coordinate { x: x as usize, y };
+						let mirror = Coordinate {
+							x: x as usize,
+							y: 2 * before - 1 - y,
+						};
+						(smudge, mirror)
+					})
+					.collect(),
+			});
+		vertical.chain(horizontal).collect()
+	}
+
+	/// Draws the pattern with every smudge flipped to match its mirror, and the line of reflection drawn between the
+	/// mirrored columns as `|` or rows as `-`.
+	fn corrected(&self, reflection: &Reflection) -> Vec<String> {
+		let mut rows = self.rows.clone();
+		for (smudge, _) in reflection.smudges.iter() {
+			rows[smudge.y] ^= 1 << smudge.x;
+		}
+
+		let mut lines: Vec<String> = Vec::new();
+		for (y, row) in rows.iter().enumerate() {
+			if reflection.axis == Axis::Horizontal && y == reflection.before {
+				lines.push("-".repeat(self.columns.len()));
 			}
-			if found_single_difference {
-				return Some(y);
+			let mut line = String::new();
+			for x in 0..self.columns.len() {
+				if reflection.axis == Axis::Vertical && x == reflection.before {
+					line.push('|');
+				}
+				line.push(if row & (1 << x) != 0 { '#' } else { '.' });
 			}
+			lines.push(line);
 		}
-		None
+		lines
 	}
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+	let mut smudges = 1;
+	let mut show_lines = false;
+	let mut show_corrected = false;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--smudges" => smudges = args.next().ok_or("--smudges requires a value")?.parse()?,
+			"--lines" => show_lines = true,
+			"--corrected" => show_corrected = true,
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
 	let grids = {
 		let input = fs::read_to_string("input.txt")?;
 
 		let mut grids: Vec<Grid> = Vec::new();
-		let mut grid_start: usize = 0;
-		let mut current_grid = Grid::new();
-		for (y, line) in input.lines().enumerate() {
+		let mut pattern: Vec<&str> = Vec::new();
+		for line in input.lines().chain([""]) {
 			if line.is_empty() {
-				grids.push(current_grid);
-				current_grid = Grid::new();
-				grid_start = y + 1;
-				continue;
-			}
-
-			for (x, c) in line.chars().enumerate() {
-				if c == '#' {
-					current_grid.add_coordinate(x, y - grid_start);
+				if !pattern.is_empty() {
+					grids.push(Grid::parse(&pattern)?);
+					pattern.clear();
 				}
+				continue;
 			}
-		}
-		if !current_grid.coords_by_x.is_empty() {
-			grids.push(current_grid);
+			pattern.push(line);
 		}
 
 		grids
 	};
 
-	let mut single_lines = 0;
-	for grid in grids.iter() {
-		let x_point = grid.find_x_reflection();
-		if let Some(x) = x_point {
-			single_lines += x;
-			continue;
+	let mut summary = 0;
+	for (index, grid) in grids.iter().enumerate() {
+		let reflections = grid.reflections(smudges);
+		if show_lines {
+			if reflections.is_empty() {
+				println!("Pattern {}: no reflections", index + 1);
+			}
+			for reflection in reflections.iter() {
+				println!("Pattern {}: {}", index + 1, reflection);
+			}
 		}
-
-		let y_point = grid.find_y_reflection();
-		if let Some(y) = y_point {
-			single_lines += y * 100;
+		if show_corrected {
+			for reflection in reflections.iter() {
+				println!("Pattern {} corrected:", index + 1);
+				for line in grid.corrected(reflection) {
+					println!("{}", line);
+				}
+				println!();
+			}
 		}
+		// Like the puzzle, only the first line counts, checking vertical lines before horizontal ones
+		summary += reflections.first().map(|reflection| reflection.summary()).unwrap_or(0);
 	}
 
-	println!("{}", single_lines);
+	println!("{}", summary);
 
 	Ok(())
 }
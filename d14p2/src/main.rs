@@ -1,198 +1,207 @@
-use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::env;
 use std::error::Error;
 use std::fs;
-
-const CYCLE_COUNT: usize = 1000000000;
-
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-enum RockType {
-	Round,
-	Cube,
+use std::ops::Range;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Direction {
+	North,
+	West,
+	South,
+	East,
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-struct Coordinate {
-	x: usize,
-	y: usize,
-}
+impl Direction {
+	const ALL: [Self; 4] = [Self::North, Self::West, Self::South, Self::East];
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-struct Rock {
-	rock_type: RockType,
-	coordinate: Coordinate,
+	fn parse(c: char) -> Result<Self, Box<dyn Error>> {
+		match c {
+			'N' => Ok(Self::North),
+			'W' => Ok(Self::West),
+			'S' => Ok(Self::South),
+			'E' => Ok(Self::East),
+			_ => Err(format!("Unknown direction: {}", c).into()),
+		}
+	}
 }
 
-fn sort_by_x(lhs: &Rock, rhs: &Rock) -> Ordering {
-	lhs.coordinate
-		.x
-		.cmp(&rhs.coordinate.x)
-		.then_with(|| lhs.coordinate.y.cmp(&rhs.coordinate.y))
+/// How rocks move when the platform is tilted one way. Each run is a range of `order` listing the open cells between
+/// two cube rocks (or the platform's edges), nearest the edge being tilted towards first.
+struct Tilt {
+	order: Vec<usize>,
+	runs: Vec<Range<usize>>,
 }
 
-fn sort_by_y(lhs: &Rock, rhs: &Rock) -> Ordering {
-	lhs.coordinate
-		.y
-		.cmp(&rhs.coordinate.y)
-		.then_with(|| lhs.coordinate.x.cmp(&rhs.coordinate.x))
+/// The fixed parts of the platform, with the round rocks kept separately as one flag per cell so that any state can be
+/// tilted, hashed and compared cheaply.
+struct Platform {
+	width: usize,
+	height: usize,
+	tilts: Vec<Tilt>,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-	let (mut rocks_by_x, mut rocks_by_y, height, width) = {
-		let input = fs::read_to_string("input.txt")?;
-
-		let mut rocks: Vec<Rock> = Vec::new();
-		let mut height: usize = 0;
-		let mut width: usize = 0;
-		for (y, line) in input.lines().enumerate() {
-			height = height.max(y + 1);
-			for (x, c) in line.chars().enumerate() {
-				width = width.max(x + 1);
-				let rock_type = match c {
-					'O' => RockType::Round,
-					'#' => RockType::Cube,
-					_ => continue,
+impl Platform {
+	fn new(width: usize, height: usize, cubes: &[bool]) -> Self {
+		let tilts = Direction::ALL
+			.iter()
+			.map(|direction| {
+				// Lines are walked from the edge being tilted towards, so each is a list of cells in the order rocks
+				// settle into them.
+				let lines: Vec<Vec<usize>> = match direction {
+					Direction::North => (0..width)
+						.map(|x| (0..height).map(|y| y * width + x).collect())
+						.collect(),
+					Direction::South => (0..width)
+						.map(|x| (0..height).rev().map(|y| y * width + x).collect())
+						.collect(),
+					Direction::West => (0..height)
+						.map(|y| (0..width).map(|x| y * width + x).collect())
+						.collect(),
+					Direction::East => (0..height)
+						.map(|y| (0..width).rev().map(|x| y * width + x).collect())
+						.collect(),
 				};
-				rocks.push(Rock {
-					rock_type,
-					coordinate: Coordinate { x, y },
-				});
-			}
-		}
 
-		let mut rocks_by_x = rocks.clone();
-		let mut rocks_by_y = rocks;
+				let mut order: Vec<usize> = Vec::with_capacity(width * height);
+				let mut runs: Vec<Range<usize>> = Vec::new();
+				for line in lines {
+					let mut run_start = order.len();
+					for cell in line {
+						if cubes[cell] {
+							if run_start < order.len() {
+								runs.push(run_start..order.len());
+							}
+							run_start = order.len();
+						} else {
+							order.push(cell);
+						}
+					}
+					if run_start < order.len() {
+						runs.push(run_start..order.len());
+					}
+				}
+				Tilt { order, runs }
+			})
+			.collect();
 
-		rocks_by_x.sort_unstable_by(sort_by_x);
-		rocks_by_y.sort_unstable_by(sort_by_y);
+		Self { width, height, tilts }
+	}
 
-		(rocks_by_x, rocks_by_y, height, width)
-	};
-	
-	let rock_count = rocks_by_x.len();
-
-	let mut seen_before: HashMap<Vec<Rock>, usize> = HashMap::new();
-	let mut cycle_info: Option<(usize, usize)> = None;
-	for cycle in 1..=CYCLE_COUNT {
-		// North
-		let mut new_rocks: Vec<Rock> = Vec::new();
-		for rock in rocks_by_x.iter() {
-			if rock.rock_type == RockType::Cube {
-				new_rocks.push(rock.clone());
-				continue;
-			}
-			let mut new_rock = rock.clone();
-			if let Some(previous_rock) = new_rocks.last() {
-				if previous_rock.coordinate.x == new_rock.coordinate.x {
-					new_rock.coordinate.y = previous_rock.coordinate.y + 1;
-				} else {
-					new_rock.coordinate.y = 0;
-				}
-			} else {
-				new_rock.coordinate.y = 0;
+	fn tilt(&self, round_rocks: &mut [bool], direction: Direction) {
+		let tilt = &self.tilts[direction as usize];
+		for run in tilt.runs.iter() {
+			let cells = &tilt.order[run.clone()];
+			let round_count = cells.iter().filter(|cell| round_rocks[**cell]).count();
+			for (index, cell) in cells.iter().enumerate() {
+				round_rocks[*cell] = index < round_count;
 			}
-			new_rocks.push(new_rock);
 		}
+	}
 
-		// West
-		rocks_by_y = std::mem::take(&mut new_rocks);
-		rocks_by_y.sort_unstable_by(sort_by_y);
-		for rock in rocks_by_y.iter() {
-			if rock.rock_type == RockType::Cube {
-				new_rocks.push(rock.clone());
-				continue;
-			}
-			let mut new_rock = rock.clone();
-			if let Some(previous_rock) = new_rocks.last() {
-				if previous_rock.coordinate.y == new_rock.coordinate.y {
-					new_rock.coordinate.x = previous_rock.coordinate.x + 1;
-				} else {
-					new_rock.coordinate.x = 0;
+	/// Sums, for every round rock, its distance from the edge opposite the given one, counting the rock's own row or
+	/// column.
+	fn load(&self, round_rocks: &[bool], edge: Direction) -> usize {
+		round_rocks
+			.iter()
+			.enumerate()
+			.filter(|(_, round)| **round)
+			.map(|(cell, _)| {
+				let (x, y) = (cell % self.width, cell / self.width);
+				match edge {
+					Direction::North => self.height - y,
+					Direction::South => y + 1,
+					Direction::West => self.width - x,
+					Direction::East => x + 1,
 				}
-			} else {
-				new_rock.coordinate.x = 0;
-			}
-			new_rocks.push(new_rock);
+			})
+			.sum()
+	}
+}
+
+/// Parses a count given either as a plain number or in scientific notation, such as `1e9`.
+fn parse_count(description: &str) -> Result<usize, Box<dyn Error>> {
+	match description.split_once(['e', 'E']) {
+		Some((mantissa, exponent)) => {
+			let mantissa: usize = mantissa.parse()?;
+			let exponent: u32 = exponent.parse()?;
+			10usize
+				.checked_pow(exponent)
+				.and_then(|scale| mantissa.checked_mul(scale))
+				.ok_or_else(|| format!("Count is too large: {}", description).into())
 		}
+		None => Ok(description.parse()?),
+	}
+}
 
-		// South
-		rocks_by_x = std::mem::take(&mut new_rocks);
-		rocks_by_x.sort_unstable_by(sort_by_x);
-		for rock in rocks_by_x.iter().rev() {
-			if rock.rock_type == RockType::Cube {
-				new_rocks.push(rock.clone());
-				continue;
+fn main() -> Result<(), Box<dyn Error>> {
+	let mut spin: Vec<Direction> = Direction::ALL.to_vec();
+	let mut cycle_count: usize = 1000000000;
+	let mut edge = Direction::North;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--spin" => {
+				let description = args.next().ok_or("--spin requires a value")?;
+				spin = description.chars().map(Direction::parse).collect::<Result<_, _>>()?;
 			}
-			let mut new_rock = rock.clone();
-			if let Some(previous_rock) = new_rocks.last() {
-				if previous_rock.coordinate.x == new_rock.coordinate.x {
-					new_rock.coordinate.y = previous_rock.coordinate.y - 1;
-				} else {
-					new_rock.coordinate.y = height - 1;
-				}
-			} else {
-				new_rock.coordinate.y = height - 1;
+			"--cycles" => cycle_count = parse_count(&args.next().ok_or("--cycles requires a value")?)?,
+			"--edge" => {
+				let description = args.next().ok_or("--edge requires a value")?;
+				let mut directions = description.chars();
+				let (Some(direction), None) = (directions.next(), directions.next()) else {
+					return Err(format!("Edges are a single direction: {}", description).into());
+				};
+				edge = Direction::parse(direction)?;
 			}
-			new_rocks.push(new_rock);
+			other => return Err(format!("Unknown argument: {}", other).into()),
 		}
+	}
+	if spin.is_empty() {
+		return Err("Spins need at least one direction".into());
+	}
+
+	let (platform, mut round_rocks) = {
+		let input = fs::read_to_string("input.txt")?;
 
-		// East
-		rocks_by_y = std::mem::take(&mut new_rocks);
-		rocks_by_y.sort_unstable_by(sort_by_y);
-		for rock in rocks_by_y.iter().rev() {
-			if rock.rock_type == RockType::Cube {
-				new_rocks.push(rock.clone());
-				continue;
+		let lines: Vec<&str> = input.lines().filter(|s| !s.is_empty()).collect();
+		let height = lines.len();
+		let width = lines.first().map(|line| line.len()).unwrap_or(0);
+		let mut cubes: Vec<bool> = Vec::with_capacity(width * height);
+		let mut round_rocks: Vec<bool> = Vec::with_capacity(width * height);
+		for line in lines {
+			if line.len() != width {
+				return Err(format!("Platform rows have different lengths: {}", line).into());
 			}
-			let mut new_rock = rock.clone();
-			if let Some(previous_rock) = new_rocks.last() {
-				if previous_rock.coordinate.y == new_rock.coordinate.y {
-					new_rock.coordinate.x = previous_rock.coordinate.x - 1;
-				} else {
-					new_rock.coordinate.x = width - 1;
+			for c in line.chars() {
+				if !matches!(c, 'O' | '#' | '.') {
+					return Err(format!("Unknown platform cell: {}", c).into());
 				}
-			} else {
-				new_rock.coordinate.x = width - 1;
+				round_rocks.push(c == 'O');
+				cubes.push(c == '#');
 			}
-			new_rocks.push(new_rock);
 		}
 
-		rocks_by_x = new_rocks.clone();
-		rocks_by_y = new_rocks;
-
-		rocks_by_x.sort_unstable_by(sort_by_x);
-		rocks_by_y.sort_unstable_by(sort_by_y);
-
-		match seen_before.get(&rocks_by_y) {
-			Some(original_cycle) => {
-				cycle_info = Some((*original_cycle, cycle));
-				break;
-			}
-			None => seen_before.insert(rocks_by_y.clone(), cycle),
-		};
-
-		assert_eq!(rock_count, rocks_by_x.len());
-	}
+		(Platform::new(width, height, &cubes), round_rocks)
+	};
 
-	if let Some((cycle_start, cycle_repeat)) = cycle_info {
-		let cycle_length = cycle_repeat - cycle_start;
-		let cycle_count_aligned = CYCLE_COUNT - cycle_start;
-		let cycle_past_start = cycle_count_aligned % cycle_length;
-		let final_cycle_equivalence_number = cycle_start + cycle_past_start;
-		for (seen_rocks_by_y, cycle_number) in seen_before.iter() {
-			if *cycle_number == final_cycle_equivalence_number {
-				rocks_by_y = seen_rocks_by_y.clone();
-				break;
-			}
+	// Spin until a state repeats, then skip ahead by whole periods to the state the final cycle lands on
+	let mut seen_before: HashMap<Vec<bool>, usize> = HashMap::new();
+	let mut history: Vec<Vec<bool>> = Vec::new();
+	for cycle in 0..cycle_count {
+		if let Some(cycle_start) = seen_before.insert(round_rocks.clone(), cycle) {
+			let cycle_length = cycle - cycle_start;
+			round_rocks = history.swap_remove(cycle_start + (cycle_count - cycle_start) % cycle_length);
+			break;
 		}
-	}
+		history.push(round_rocks.clone());
 
-	let mut load = 0;
-	for rock in rocks_by_y.iter().filter(|rock| rock.rock_type == RockType::Round) {
-		load += height - rock.coordinate.y;
+		for direction in spin.iter() {
+			platform.tilt(&mut round_rocks, *direction);
+		}
 	}
 
-	println!("{}", load);
+	println!("{}", platform.load(&round_rocks, edge));
 
 	Ok(())
 }
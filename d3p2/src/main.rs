@@ -1,14 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::error::Error;
 use std::fs;
 
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 struct Coordinate {
 	x: u32,
 	y: u32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 struct Coordinates {
 	x: (u32, u32),
 	y: u32,
@@ -20,11 +21,94 @@ struct Number {
 	position: Coordinates,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-	let (numbers, gears) = {
-		let input = fs::read_to_string("input.txt")?;
+enum SymbolSet {
+	Any,
+	Only(HashSet<char>),
+}
+
+impl SymbolSet {
+	fn contains(&self, symbol: char) -> bool {
+		match self {
+			Self::Any => true,
+			Self::Only(symbols) => symbols.contains(&symbol),
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+enum Reduction {
+	Product,
+	Sum,
+}
+
+impl Reduction {
+	fn apply(&self, numbers: &[Number]) -> u64 {
+		let values = numbers.iter().map(|number| number.value as u64);
+		match self {
+			Self::Product => values.product(),
+			Self::Sum => values.sum(),
+		}
+	}
+}
+
+struct Gear {
+	symbol: char,
+	position: Coordinate,
+	numbers: Vec<Number>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CellStyle {
+	Plain,
+	Symbol,
+	PartNumber,
+	NonPartNumber,
+	GearSymbol,
+	GearNumber,
+}
+
+impl CellStyle {
+	fn ansi_code(&self) -> Option<&'static str> {
+		match self {
+			Self::Plain => None,
+			Self::Symbol => Some("1"),
+			Self::PartNumber => Some("32"),
+			Self::NonPartNumber => Some("31"),
+			Self::GearSymbol => Some("1;33"),
+			Self::GearNumber => Some("1;36"),
+		}
+	}
+
+	fn html_class(&self) -> Option<&'static str> {
+		match self {
+			Self::Plain => None,
+			Self::Symbol => Some("symbol"),
+			Self::PartNumber => Some("part"),
+			Self::NonPartNumber => Some("non-part"),
+			Self::GearSymbol => Some("gear"),
+			Self::GearNumber => Some("gear-number"),
+		}
+	}
+}
 
-		let mut gears: Vec<Coordinate> = Vec::new();
+const HTML_STYLE: &str = "body { background: #0f0f23; color: #666; }
+.symbol { color: #ccc; font-weight: bold; }
+.part { color: #00cc00; }
+.non-part { color: #ff4040; }
+.gear { color: #ffff66; font-weight: bold; }
+.gear-number { color: #00cccc; font-weight: bold; }";
+
+struct Schematic {
+	lines: Vec<Vec<char>>,
+	numbers: Vec<Number>,
+	symbols: HashMap<Coordinate, char>,
+	number_coords: HashMap<Coordinate, Coordinates>,
+	number_positions: HashMap<Coordinates, Number>,
+}
+
+impl Schematic {
+	fn parse(input: &str) -> Self {
+		let mut symbols: HashMap<Coordinate, char> = HashMap::new();
 		let mut numbers: Vec<Number> = Vec::new();
 		for (y, line) in input.lines().enumerate() {
 			let y = y as u32;
@@ -50,9 +134,9 @@ fn main() -> Result<(), Box<dyn Error>> {
 					number_start = None;
 				}
 
-				if c == '*' {
+				if c != '.' && !c.is_ascii_digit() {
 					let coord = Coordinate { x, y };
-					gears.push(coord);
+					symbols.insert(coord, c);
 				}
 			}
 			if let Some(start) = number_start {
@@ -66,61 +150,296 @@ fn main() -> Result<(), Box<dyn Error>> {
 			}
 		}
 
-		(numbers, gears)
-	};
+		let mut number_coords: HashMap<Coordinate, Coordinates> = HashMap::new();
+		let mut number_positions: HashMap<Coordinates, Number> = HashMap::new();
+		for number in numbers.iter() {
+			let (low_x, high_x) = number.position.x;
+			for x in low_x..high_x {
+				let coord = Coordinate {
+					x,
+					y: number.position.y,
+				};
+				number_coords.insert(coord, number.position);
+			}
+			number_positions.insert(number.position, *number);
+		}
 
-	let mut number_coords: HashMap<Coordinate, Number> = HashMap::new();
-	for number in numbers.iter() {
-		let (low_x, high_x) = number.position.x;
-		for x in low_x..high_x {
-			let coord = Coordinate {
-				x,
-				y: number.position.y,
-			};
-			number_coords.insert(coord, *number);
+		let lines = input.lines().map(|line| line.chars().collect()).collect();
+
+		Self {
+			lines,
+			numbers,
+			symbols,
+			number_coords,
+			number_positions,
+		}
+	}
+
+	/// Gets every distinct number touching the given coordinate, in reading order.
+	fn adjacent_numbers(&self, coord: &Coordinate) -> Vec<Number> {
+		let low_x = coord.x.saturating_sub(1);
+		let high_x = coord.x + 1;
+		let low_y = coord.y.saturating_sub(1);
+		let high_y = coord.y + 1;
+
+		let mut seen_positions: HashSet<Coordinates> = HashSet::new();
+		let mut numbers: Vec<Number> = Vec::new();
+		for y in low_y..=high_y {
+			for x in low_x..=high_x {
+				let search_coord = Coordinate { x, y };
+				let Some(position) = self.number_coords.get(&search_coord) else {
+					continue;
+				};
+				if seen_positions.insert(*position) {
+					numbers.push(self.number_positions[position]);
+				}
+			}
 		}
+		numbers
 	}
 
-	let mut gear_ratio_sum = 0;
+	fn is_part_number(&self, number: &Number, symbols: &SymbolSet) -> bool {
+		let (low_x, high_x) = number.position.x;
+		let low_x = low_x.saturating_sub(1);
 
-	for gear in gears.iter() {
-		let low_x = gear.x.saturating_sub(1);
-		let high_x = gear.x + 1;
-		let low_y = gear.y.saturating_sub(1);
-		let high_y = gear.y + 1;
+		let low_y = number.position.y.saturating_sub(1);
+		let high_y = number.position.y + 1;
 
-		let mut first_number: Option<u32> = None;
-		let mut second_number: Option<u32> = None;
-		'search: for x in low_x..=high_x {
+		for x in low_x..=high_x {
 			for y in low_y..=high_y {
 				let coord = Coordinate { x, y };
-				let found_number = number_coords.get(&coord);
-				let Some(found_number) = found_number else {
-					continue;
+				if let Some(symbol) = self.symbols.get(&coord) {
+					if symbols.contains(*symbol) {
+						return true;
+					}
+				}
+			}
+		}
+		false
+	}
+
+	fn part_numbers(&self, symbols: &SymbolSet) -> Vec<Number> {
+		self.numbers
+			.iter()
+			.filter(|number| self.is_part_number(number, symbols))
+			.copied()
+			.collect()
+	}
+
+	/// Gets every matching symbol with exactly `arity` distinct adjacent numbers, in reading order.
+	fn gears(&self, symbols: &SymbolSet, arity: usize) -> Vec<Gear> {
+		let mut gear_positions: Vec<(&Coordinate, &char)> = self
+			.symbols
+			.iter()
+			.filter(|(_, symbol)| symbols.contains(**symbol))
+			.collect();
+		gear_positions.sort_unstable_by_key(|(coord, _)| (coord.y, coord.x));
+
+		let mut gears: Vec<Gear> = Vec::new();
+		for (position, symbol) in gear_positions {
+			let numbers = self.adjacent_numbers(position);
+			if numbers.len() == arity {
+				gears.push(Gear {
+					symbol: *symbol,
+					position: *position,
+					numbers,
+				});
+			}
+		}
+		gears
+	}
+
+	fn cell_styles(&self, gears: &[Gear]) -> HashMap<Coordinate, CellStyle> {
+		let mut styles: HashMap<Coordinate, CellStyle> = HashMap::new();
+		for coord in self.symbols.keys() {
+			styles.insert(*coord, CellStyle::Symbol);
+		}
+		for number in self.numbers.iter() {
+			let style = if self.is_part_number(number, &SymbolSet::Any) {
+				CellStyle::PartNumber
+			} else {
+				CellStyle::NonPartNumber
+			};
+			mark_number(&mut styles, number, style);
+		}
+		for gear in gears.iter() {
+			styles.insert(gear.position, CellStyle::GearSymbol);
+			for number in gear.numbers.iter() {
+				mark_number(&mut styles, number, CellStyle::GearNumber);
+			}
+		}
+		styles
+	}
+
+	/// Groups each line into runs of characters sharing a style.
+	fn styled_runs(&self, gears: &[Gear]) -> Vec<Vec<(CellStyle, String)>> {
+		let styles = self.cell_styles(gears);
+		let mut rendered_lines: Vec<Vec<(CellStyle, String)>> = Vec::new();
+		for (y, line) in self.lines.iter().enumerate() {
+			let mut runs: Vec<(CellStyle, String)> = Vec::new();
+			for (x, c) in line.iter().enumerate() {
+				let coord = Coordinate {
+					x: x as u32,
+					y: y as u32,
 				};
-				if first_number == Some(found_number.value) || second_number == Some(found_number.value) {
-					continue;
+				let style = styles.get(&coord).copied().unwrap_or(CellStyle::Plain);
+				match runs.last_mut() {
+					Some((last_style, run)) if *last_style == style => run.push(*c),
+					_ => runs.push((style, c.to_string())),
 				}
-				if first_number.is_some() && second_number.is_some() {
-					// This is a third number
-					first_number = None;
-					second_number = None;
-					break 'search;
+			}
+			rendered_lines.push(runs);
+		}
+		rendered_lines
+	}
+
+	fn render_ansi(&self, gears: &[Gear]) -> String {
+		let mut output = String::new();
+		for runs in self.styled_runs(gears) {
+			for (style, run) in runs {
+				match style.ansi_code() {
+					Some(code) => output.push_str(&format!("\x1b[{}m{}\x1b[0m", code, run)),
+					None => output.push_str(&run),
 				}
+			}
+			output.push('\n');
+		}
+		output
+	}
+
+	fn render_html(&self, gears: &[Gear]) -> String {
+		let mut output = String::new();
+		output.push_str(
+			"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Engine schematic</title>\n<style>\n",
+		);
+		output.push_str(HTML_STYLE);
+		output.push_str("\n</style>\n</head>\n<body>\n<pre>\n");
+		for runs in self.styled_runs(gears) {
+			for (style, run) in runs {
+				let run = html_escape(&run);
+				match style.html_class() {
+					Some(class) => output.push_str(&format!("<span class=\"{}\">{}</span>", class, run)),
+					None => output.push_str(&run),
+				}
+			}
+			output.push('\n');
+		}
+		output.push_str("</pre>\n</body>\n</html>\n");
+		output
+	}
+}
 
-				if first_number.is_some() {
-					second_number = Some(found_number.value);
+fn mark_number(styles: &mut HashMap<Coordinate, CellStyle>, number: &Number, style: CellStyle) {
+	let (low_x, high_x) = number.position.x;
+	for x in low_x..high_x {
+		let coord = Coordinate {
+			x,
+			y: number.position.y,
+		};
+		styles.insert(coord, style);
+	}
+}
+
+fn html_escape(text: &str) -> String {
+	let mut escaped = String::new();
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			_ => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+	let mut gear_symbols: HashSet<char> = HashSet::new();
+	let mut any_symbol = false;
+	let mut arity: usize = 2;
+	let mut reduction = Reduction::Product;
+	let mut list_parts = false;
+	let mut list_gears = false;
+	let mut render = false;
+	let mut html_path: Option<String> = None;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--symbols" => {
+				let symbols = args.next().ok_or("--symbols requires a value")?;
+				if symbols == "any" {
+					any_symbol = true;
 				} else {
-					first_number = Some(found_number.value);
+					gear_symbols.extend(symbols.chars());
+				}
+			}
+			"--arity" => arity = args.next().ok_or("--arity requires a value")?.parse()?,
+			"--reduce" => {
+				reduction = match args.next().ok_or("--reduce requires a value")?.as_str() {
+					"product" => Reduction::Product,
+					"sum" => Reduction::Sum,
+					other => return Err(format!("Unknown reduction: {}", other).into()),
 				}
 			}
+			"--parts" => list_parts = true,
+			"--gears" => list_gears = true,
+			"--render" => render = true,
+			"--html" => html_path = Some(args.next().ok_or("--html requires an output path")?),
+			other => return Err(format!("Unknown argument: {}", other).into()),
 		}
-		if let (Some(first), Some(second)) = (first_number, second_number) {
-			let gear_ratio = first * second;
-			gear_ratio_sum += gear_ratio;
+	}
+	let gear_symbols = if any_symbol {
+		SymbolSet::Any
+	} else if gear_symbols.is_empty() {
+		SymbolSet::Only(HashSet::from(['*']))
+	} else {
+		SymbolSet::Only(gear_symbols)
+	};
+
+	let schematic = {
+		let input = fs::read_to_string("input.txt")?;
+		Schematic::parse(&input)
+	};
+
+	if list_parts {
+		let part_numbers = schematic.part_numbers(&SymbolSet::Any);
+		for number in part_numbers.iter() {
+			println!(
+				"Part number {} at ({}-{}, {})",
+				number.value,
+				number.position.x.0,
+				number.position.x.1 - 1,
+				number.position.y
+			);
 		}
+		let part_number_sum: u64 = part_numbers.iter().map(|number| number.value as u64).sum();
+		println!("Part number sum: {}", part_number_sum);
 	}
 
+	let gears = schematic.gears(&gear_symbols, arity);
+	if list_gears {
+		for gear in gears.iter() {
+			let values: Vec<String> = gear.numbers.iter().map(|number| number.value.to_string()).collect();
+			println!(
+				"Gear {} at ({}, {}): {}",
+				gear.symbol,
+				gear.position.x,
+				gear.position.y,
+				values.join(", ")
+			);
+		}
+	}
+
+	if render {
+		print!("{}", schematic.render_ansi(&gears));
+	}
+	if let Some(path) = html_path {
+		fs::write(path, schematic.render_html(&gears))?;
+	}
+
+	let gear_ratio_sum: u64 = gears.iter().map(|gear| reduction.apply(&gear.numbers)).sum();
+
 	println!("{}", gear_ratio_sum);
 
 	Ok(())
@@ -1,36 +1,82 @@
+use std::collections::HashSet;
+use std::env;
 use std::error::Error;
 use std::fs;
 
 struct Card {
-	winning_numbers: Vec<u32>,
+	id: u32,
+	winning_numbers: HashSet<u32>,
 	play_numbers: Vec<u32>,
 }
 
 impl Card {
-	fn matches(&self) -> u32 {
-		let mut matches = 0;
-		for number in self.play_numbers.iter() {
-			if self.winning_numbers.iter().any(|num| *num == *number) {
-				matches += 1;
-			}
+	fn matches(&self) -> usize {
+		self.play_numbers
+			.iter()
+			.filter(|number| self.winning_numbers.contains(number))
+			.count()
+	}
+}
+
+struct CardCascade {
+	copies: u64,
+	sources: Vec<(usize, u64)>,
+	produced: u64,
+}
+
+/// Processes every card in order, tracking which earlier cards each copy was won from.
+fn cascade(cards: &[Card]) -> Vec<CardCascade> {
+	let mut cascades: Vec<CardCascade> = cards
+		.iter()
+		.map(|_| CardCascade {
+			copies: 1,
+			sources: Vec::new(),
+			produced: 0,
+		})
+		.collect();
+
+	for (card_index, card) in cards.iter().enumerate() {
+		let num_to_process = cascades[card_index].copies;
+		let won_range = (card_index + 1)..(card_index + 1 + card.matches()).min(cards.len());
+		cascades[card_index].produced = num_to_process * won_range.len() as u64;
+		for won_card in cascades[won_range].iter_mut() {
+			won_card.copies += num_to_process;
+			won_card.sources.push((card_index, num_to_process));
 		}
-		matches
 	}
+
+	cascades
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+	let mut explain = false;
+	let mut top: Option<usize> = None;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--explain" => explain = true,
+			"--top" => top = Some(args.next().ok_or("--top requires a value")?.parse()?),
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
 	let cards = {
 		let input = fs::read_to_string("input.txt")?;
 
 		let mut cards: Vec<Card> = Vec::new();
 		for line in input.lines() {
-			let numbers = line.split(": ").nth(1).unwrap().to_string();
+			let mut line_parts = line.split(": ");
+			let id = line_parts.next().unwrap();
+			let numbers = line_parts.next().unwrap();
+			assert!(line_parts.next().is_none());
+
+			let id: u32 = id.strip_prefix("Card").unwrap().trim().parse()?;
 			let mut parts = numbers.split(" | ");
 			let winning_numbers = parts.next().unwrap();
 			let play_numbers = parts.next().unwrap();
 			assert!(parts.next().is_none());
 
-			let winning_numbers: Vec<u32> = winning_numbers
+			let winning_numbers: HashSet<u32> = winning_numbers
 				.split(' ')
 				.filter(|s| !s.is_empty())
 				.map(|s| s.parse().unwrap())
@@ -42,6 +88,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 				.collect();
 
 			cards.push(Card {
+				id,
 				winning_numbers,
 				play_numbers,
 			});
@@ -49,18 +96,40 @@ fn main() -> Result<(), Box<dyn Error>> {
 		cards
 	};
 
-	let mut process_counts: Vec<u32> = cards.iter().map(|_| 1).collect();
-	let mut total_cards = 0;
+	let cascades = cascade(&cards);
 
-	for (card_index, card) in cards.iter().enumerate() {
-		let num_to_process = process_counts[card_index];
-		total_cards += num_to_process;
-		let matches = card.matches();
-		for count in process_counts.iter_mut().skip(card_index + 1).take(matches as usize) {
-			*count += num_to_process;
+	if explain {
+		for (card, card_cascade) in cards.iter().zip(cascades.iter()) {
+			let sources: Vec<String> = card_cascade
+				.sources
+				.iter()
+				.map(|(source_index, copies)| format!("{} from card {}", copies, cards[*source_index].id))
+				.collect();
+			if sources.is_empty() {
+				println!("Card {}: {} copies (original)", card.id, card_cascade.copies);
+			} else {
+				println!(
+					"Card {}: {} copies (original, {})",
+					card.id,
+					card_cascade.copies,
+					sources.join(", ")
+				);
+			}
 		}
 	}
 
+	if let Some(top) = top {
+		let mut producers: Vec<(&Card, &CardCascade)> = cards.iter().zip(cascades.iter()).collect();
+		producers.sort_by(|(lhs_card, lhs), (rhs_card, rhs)| {
+			rhs.produced.cmp(&lhs.produced).then(lhs_card.id.cmp(&rhs_card.id))
+		});
+		for (card, card_cascade) in producers.into_iter().take(top) {
+			println!("Card {} produced {} copies", card.id, card_cascade.produced);
+		}
+	}
+
+	let total_cards: u64 = cascades.iter().map(|card_cascade| card_cascade.copies).sum();
+
 	println!("{}", total_cards);
 
 	Ok(())
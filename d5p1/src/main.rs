@@ -1,17 +1,18 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
 use std::error::Error;
 use std::fs;
 use std::ops::Range;
 
-#[derive(Eq, PartialEq)]
-enum ParseLocation {
-	Seeds,
-	SeedSoil,
-	SoilFertilizer,
-	FertilizerWater,
-	WaterLight,
-	LightTemp,
-	TempHumidity,
-	HumidityLocation,
+struct AlmanacMap {
+	source: String,
+	destination: String,
+	entries: Vec<(Range<u64>, u64)>,
+}
+
+struct Almanac {
+	seeds: Vec<u64>,
+	maps: Vec<AlmanacMap>,
 }
 
 fn map_num(map: &[(Range<u64>, u64)], input: u64) -> u64 {
@@ -24,72 +25,178 @@ fn map_num(map: &[(Range<u64>, u64)], input: u64) -> u64 {
 	input
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-	let input = fs::read_to_string("input.txt")?;
-
-	let mut seeds: Vec<u64> = Vec::new();
-	let mut seed_soil_map: Vec<(Range<u64>, u64)> = Vec::new();
-	let mut soil_fertilizer_map: Vec<(Range<u64>, u64)> = Vec::new();
-	let mut fertilizer_water_map: Vec<(Range<u64>, u64)> = Vec::new();
-	let mut water_light_map: Vec<(Range<u64>, u64)> = Vec::new();
-	let mut light_temperature_map: Vec<(Range<u64>, u64)> = Vec::new();
-	let mut temperature_humidity_map: Vec<(Range<u64>, u64)> = Vec::new();
-	let mut humidity_location_map: Vec<(Range<u64>, u64)> = Vec::new();
-
-	let mut parse_location = ParseLocation::Seeds;
-	for line in input.lines().filter(|s| !s.is_empty()) {
-		match line {
-			"seed-to-soil map:" => parse_location = ParseLocation::SeedSoil,
-			"soil-to-fertilizer map:" => parse_location = ParseLocation::SoilFertilizer,
-			"fertilizer-to-water map:" => parse_location = ParseLocation::FertilizerWater,
-			"water-to-light map:" => parse_location = ParseLocation::WaterLight,
-			"light-to-temperature map:" => parse_location = ParseLocation::LightTemp,
-			"temperature-to-humidity map:" => parse_location = ParseLocation::TempHumidity,
-			"humidity-to-location map:" => parse_location = ParseLocation::HumidityLocation,
-			_ => {
-				if parse_location == ParseLocation::Seeds {
-					seeds = line
-						.strip_prefix("seeds: ")
-						.unwrap()
-						.split(' ')
-						.map(|n| n.parse().unwrap())
-						.collect();
-					continue;
-				}
-				let mut number_iter = line.split(' ').map(|n| n.parse().unwrap());
-				let destination: u64 = number_iter.next().unwrap();
-				let start: u64 = number_iter.next().unwrap();
-				let range: u64 = number_iter.next().unwrap();
-				assert!(number_iter.next().is_none());
-
-				let map_ref = match parse_location {
-					ParseLocation::Seeds => unreachable!(),
-					ParseLocation::SeedSoil => &mut seed_soil_map,
-					ParseLocation::SoilFertilizer => &mut soil_fertilizer_map,
-					ParseLocation::FertilizerWater => &mut fertilizer_water_map,
-					ParseLocation::WaterLight => &mut water_light_map,
-					ParseLocation::LightTemp => &mut light_temperature_map,
-					ParseLocation::TempHumidity => &mut temperature_humidity_map,
-					ParseLocation::HumidityLocation => &mut humidity_location_map,
+/// Ensures no value falls in the source range of more than one entry, as it would be ambiguous which one applies.
+fn check_overlaps(map: &[(Range<u64>, u64)]) -> Result<(), Box<dyn Error>> {
+	let mut sources: Vec<&Range<u64>> = map
+		.iter()
+		.map(|(range, _)| range)
+		.filter(|range| !range.is_empty())
+		.collect();
+	sources.sort_unstable_by_key(|range| range.start);
+	for pair in sources.windows(2) {
+		if pair[1].start < pair[0].end {
+			return Err(format!(
+				"Source ranges {}..{} and {}..{} overlap",
+				pair[0].start, pair[0].end, pair[1].start, pair[1].end
+			)
+			.into());
+		}
+	}
+	Ok(())
+}
+
+impl Almanac {
+	fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+		let mut seeds: Option<Vec<u64>> = None;
+		let mut maps: Vec<AlmanacMap> = Vec::new();
+
+		for line in input.lines().filter(|s| !s.is_empty()) {
+			if let Some(seed_list) = line.strip_prefix("seeds: ") {
+				let seed_list: Result<Vec<u64>, _> = seed_list.split(' ').map(|n| n.parse()).collect();
+				seeds = Some(seed_list?);
+			} else if let Some(header) = line.strip_suffix(" map:") {
+				let Some((source, destination)) = header.split_once("-to-") else {
+					return Err(format!("Invalid map header: {}", line).into());
+				};
+				maps.push(AlmanacMap {
+					source: source.to_string(),
+					destination: destination.to_string(),
+					entries: Vec::new(),
+				});
+			} else {
+				let Some(map) = maps.last_mut() else {
+					return Err(format!("Map entry outside of a map: {}", line).into());
+				};
+				let numbers: Result<Vec<u64>, _> = line.split(' ').map(|n| n.parse()).collect();
+				let numbers = numbers?;
+				let [destination, start, range] = numbers[..] else {
+					return Err(format!("Map entries need exactly three numbers: {}", line).into());
 				};
-				map_ref.push((start..(start + range), destination));
+				map.entries.push((start..(start + range), destination));
+			}
+		}
+
+		for map in maps.iter() {
+			if let Err(error) = check_overlaps(&map.entries) {
+				return Err(format!("Invalid {}-to-{} map: {}", map.source, map.destination, error).into());
+			}
+		}
+
+		let Some(seeds) = seeds else {
+			return Err("Almanac has no seeds".into());
+		};
+		let almanac = Self { seeds, maps };
+		almanac.validate()?;
+		Ok(almanac)
+	}
+
+	/// Ensures the maps form a DAG of categories with at most one map between any two categories.
+	fn validate(&self) -> Result<(), Box<dyn Error>> {
+		let mut edges: HashSet<(&str, &str)> = HashSet::new();
+		for map in self.maps.iter() {
+			if !edges.insert((&map.source, &map.destination)) {
+				return Err(format!("Duplicate {}-to-{} map", map.source, map.destination).into());
+			}
+		}
+
+		let mut incoming_counts: HashMap<&str, usize> = HashMap::new();
+		for map in self.maps.iter() {
+			incoming_counts.entry(&map.source).or_insert(0);
+			*incoming_counts.entry(&map.destination).or_insert(0) += 1;
+		}
+		let mut ready: Vec<&str> = incoming_counts
+			.iter()
+			.filter(|(_, count)| **count == 0)
+			.map(|(category, _)| *category)
+			.collect();
+		let mut visited = 0;
+		while let Some(category) = ready.pop() {
+			visited += 1;
+			for map in self.maps.iter().filter(|map| map.source == category) {
+				let count = incoming_counts.get_mut(map.destination.as_str()).unwrap();
+				*count -= 1;
+				if *count == 0 {
+					ready.push(&map.destination);
+				}
+			}
+		}
+		if visited != incoming_counts.len() {
+			return Err("Almanac maps contain a cycle".into());
+		}
+
+		Ok(())
+	}
+
+	/// Finds the shortest chain of maps leading from one category to another.
+	fn path(&self, from: &str, to: &str) -> Option<Vec<&AlmanacMap>> {
+		let mut previous: HashMap<&str, &AlmanacMap> = HashMap::new();
+		let mut queue: VecDeque<&str> = VecDeque::new();
+		queue.push_back(from);
+		while let Some(category) = queue.pop_front() {
+			if category == to {
+				let mut path: Vec<&AlmanacMap> = Vec::new();
+				let mut current = to;
+				while current != from {
+					let map = previous[current];
+					path.push(map);
+					current = &map.source;
+				}
+				path.reverse();
+				return Some(path);
+			}
+			for map in self.maps.iter().filter(|map| map.source == category) {
+				if map.destination != from && !previous.contains_key(map.destination.as_str()) {
+					previous.insert(&map.destination, map);
+					queue.push_back(&map.destination);
+				}
 			}
 		}
+		None
 	}
 
-	let mut closest_location = u64::MAX;
-	for seed in seeds.iter() {
-		let soil = map_num(&seed_soil_map, *seed);
-		let fertilizer = map_num(&soil_fertilizer_map, soil);
-		let water = map_num(&fertilizer_water_map, fertilizer);
-		let light = map_num(&water_light_map, water);
-		let temperature = map_num(&light_temperature_map, light);
-		let humidity = map_num(&temperature_humidity_map, temperature);
-		let location = map_num(&humidity_location_map, humidity);
-		closest_location = closest_location.min(location);
+	fn translate(&self, from: &str, to: &str, value: u64) -> Result<u64, Box<dyn Error>> {
+		let Some(path) = self.path(from, to) else {
+			return Err(format!("No chain of maps leads from {} to {}", from, to).into());
+		};
+		Ok(path.iter().fold(value, |value, map| map_num(&map.entries, value)))
 	}
+}
 
-	println!("{}", closest_location);
+fn main() -> Result<(), Box<dyn Error>> {
+	let mut from = String::from("seed");
+	let mut to = String::from("location");
+	let mut values: Vec<u64> = Vec::new();
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--from" => from = args.next().ok_or("--from requires a category")?,
+			"--to" => to = args.next().ok_or("--to requires a category")?,
+			value => values.push(value.parse()?),
+		}
+	}
+
+	let almanac = {
+		let input = fs::read_to_string("input.txt")?;
+		Almanac::parse(&input)?
+	};
+
+	if values.is_empty() {
+		if from != "seed" {
+			return Err(format!("Values to translate are required when translating from {}", from).into());
+		}
+
+		let mut closest_location = u64::MAX;
+		for seed in almanac.seeds.iter() {
+			let location = almanac.translate(&from, &to, *seed)?;
+			closest_location = closest_location.min(location);
+		}
+
+		println!("{}", closest_location);
+	} else {
+		for value in values {
+			println!("{} {} -> {} {}", from, value, to, almanac.translate(&from, &to, value)?);
+		}
+	}
 
 	Ok(())
 }
@@ -1,110 +1,426 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::ops::Range;
 
-#[derive(Eq, PartialEq)]
-enum ParseLocation {
-	Seeds,
-	SeedSoil,
-	SoilFertilizer,
-	FertilizerWater,
-	WaterLight,
-	LightTemp,
-	TempHumidity,
-	HumidityLocation,
+type Segment = (Range<i64>, i64);
+
+/// A mapping made of offset segments over sorted, non-overlapping source ranges. Values outside every segment map
+/// to themselves.
+#[derive(Clone)]
+struct PiecewiseMap {
+	segments: Vec<Segment>,
 }
 
-fn map_num(map: &[(Range<i64>, i64)], input: i64) -> i64 {
-	for (start_range, modify_by) in map.iter() {
-		if start_range.contains(&input) {
-			return input + modify_by;
+impl PiecewiseMap {
+	fn identity() -> Self {
+		Self { segments: Vec::new() }
+	}
+
+	fn new(mut segments: Vec<Segment>) -> Result<Self, Box<dyn Error>> {
+		segments.retain(|(range, _)| !range.is_empty());
+		segments.sort_unstable_by_key(|(range, _)| range.start);
+		for pair in segments.windows(2) {
+			let (previous, _) = &pair[0];
+			let (next, _) = &pair[1];
+			if next.start < previous.end {
+				return Err(format!(
+					"Source ranges {}..{} and {}..{} overlap",
+					previous.start, previous.end, next.start, next.end
+				)
+				.into());
+			}
+		}
+		Ok(Self::normalised(segments))
+	}
+
+	/// Drops identity segments and merges touching segments with the same offset.
+	fn normalised(segments: Vec<Segment>) -> Self {
+		let mut normalised: Vec<Segment> = Vec::new();
+		for (range, modify_by) in segments {
+			if modify_by == 0 || range.is_empty() {
+				continue;
+			}
+			match normalised.last_mut() {
+				Some((last_range, last_modify_by)) if last_range.end == range.start && *last_modify_by == modify_by => {
+					last_range.end = range.end
+				}
+				_ => normalised.push((range, modify_by)),
+			}
+		}
+		Self { segments: normalised }
+	}
+
+	/// Gets the segments covering the whole domain, including the identity gaps between explicit segments.
+	fn partition(&self) -> Vec<Segment> {
+		let mut partition: Vec<Segment> = Vec::new();
+		let mut position = i64::MIN;
+		for (range, modify_by) in self.segments.iter() {
+			if position < range.start {
+				partition.push((position..range.start, 0));
+			}
+			partition.push((range.clone(), *modify_by));
+			position = range.end;
+		}
+		if position < i64::MAX {
+			partition.push((position..i64::MAX, 0));
+		}
+		partition
+	}
+
+	fn apply(&self, input: i64) -> i64 {
+		let index = self.segments.partition_point(|(range, _)| range.end <= input);
+		match self.segments.get(index) {
+			Some((range, modify_by)) if range.contains(&input) => input + modify_by,
+			_ => input,
+		}
+	}
+
+	/// Pushes whole ranges through the map, splitting them wherever they cross a segment boundary.
+	/// The partition is built once and each range only visits the segments it overlaps.
+	fn apply_ranges(&self, input: &[Range<i64>]) -> Vec<Range<i64>> {
+		let partition = self.partition();
+		let mut mapped: Vec<Range<i64>> = Vec::new();
+		for range in input.iter() {
+			let first = partition.partition_point(|(segment, _)| segment.end <= range.start);
+			for (segment, modify_by) in partition[first..]
+				.iter()
+				.take_while(|(segment, _)| segment.start < range.end)
+			{
+				let overlap_start = range.start.max(segment.start);
+				let overlap_end = range.end.min(segment.end);
+				if overlap_start < overlap_end {
+					mapped.push((overlap_start + modify_by)..(overlap_end + modify_by));
+				}
+			}
 		}
+		merge_ranges(mapped)
+	}
+
+	/// Builds the single map equivalent to applying this map followed by `next`.
+	fn compose(&self, next: &PiecewiseMap) -> PiecewiseMap {
+		let next_partition = next.partition();
+		let mut segments: Vec<Segment> = Vec::new();
+		for (range, modify_by) in self.partition() {
+			let image = (range.start + modify_by)..(range.end + modify_by);
+			for (next_range, next_modify_by) in next_partition.iter() {
+				let overlap_start = image.start.max(next_range.start);
+				let overlap_end = image.end.min(next_range.end);
+				if overlap_start < overlap_end {
+					segments.push((
+						(overlap_start - modify_by)..(overlap_end - modify_by),
+						modify_by + next_modify_by,
+					));
+				}
+			}
+		}
+		segments.sort_unstable_by_key(|(range, _)| range.start);
+		Self::normalised(segments)
+	}
+
+	fn inverse(&self) -> Result<PiecewiseMap, Box<dyn Error>> {
+		let sources = merge_ranges(self.segments.iter().map(|(range, _)| range.clone()).collect());
+		let mut images: Vec<Segment> = self
+			.segments
+			.iter()
+			.map(|(range, modify_by)| ((range.start + modify_by)..(range.end + modify_by), -modify_by))
+			.collect();
+		images.sort_unstable_by_key(|(range, _)| range.start);
+		for pair in images.windows(2) {
+			let (previous, _) = &pair[0];
+			let (next, _) = &pair[1];
+			if next.start < previous.end {
+				return Err(format!(
+					"Map is not invertible: ranges {}..{} and {}..{} overlap in the destination",
+					previous.start, previous.end, next.start, next.end
+				)
+				.into());
+			}
+		}
+		if merge_ranges(images.iter().map(|(range, _)| range.clone()).collect()) != sources {
+			return Err("Map is not invertible: mapped ranges collide with unmapped values".into());
+		}
+		Ok(Self::normalised(images))
 	}
-	input
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-	let input = fs::read_to_string("input.txt")?;
-
-	let mut seeds: Vec<Range<i64>> = Vec::new();
-	let mut seed_soil_map: Vec<(Range<i64>, i64)> = Vec::new();
-	let mut soil_fertilizer_map: Vec<(Range<i64>, i64)> = Vec::new();
-	let mut fertilizer_water_map: Vec<(Range<i64>, i64)> = Vec::new();
-	let mut water_light_map: Vec<(Range<i64>, i64)> = Vec::new();
-	let mut light_temperature_map: Vec<(Range<i64>, i64)> = Vec::new();
-	let mut temperature_humidity_map: Vec<(Range<i64>, i64)> = Vec::new();
-	let mut humidity_location_map: Vec<(Range<i64>, i64)> = Vec::new();
-
-	let mut parse_location = ParseLocation::Seeds;
-	for line in input.lines().filter(|s| !s.is_empty()) {
-		match line {
-			"seed-to-soil map:" => parse_location = ParseLocation::SeedSoil,
-			"soil-to-fertilizer map:" => parse_location = ParseLocation::SoilFertilizer,
-			"fertilizer-to-water map:" => parse_location = ParseLocation::FertilizerWater,
-			"water-to-light map:" => parse_location = ParseLocation::WaterLight,
-			"light-to-temperature map:" => parse_location = ParseLocation::LightTemp,
-			"temperature-to-humidity map:" => parse_location = ParseLocation::TempHumidity,
-			"humidity-to-location map:" => parse_location = ParseLocation::HumidityLocation,
-			_ => {
-				if parse_location == ParseLocation::Seeds {
-					line.strip_prefix("seeds: ")
-						.unwrap()
-						.split(' ')
-						.fold(None, |prev, seed_step| {
-							let seed_step: i64 = seed_step.parse().unwrap();
-							match prev {
-								Some(seed) => {
-									seeds.push(seed..(seed + seed_step));
-									None
-								}
-								None => Some(seed_step),
-							}
-						});
-					continue;
+impl fmt::Display for PiecewiseMap {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(
+			f,
+			"{:>16} {:>16} {:>16} {:>16}",
+			"source start", "source end", "destination", "offset"
+		)?;
+		for (range, modify_by) in self.segments.iter() {
+			writeln!(
+				f,
+				"{:>16} {:>16} {:>16} {:>16}",
+				range.start,
+				range.end,
+				range.start + modify_by,
+				modify_by
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// A map from the almanac, along with the source ranges its entries cover. The coverage is kept separately because
+/// the map itself drops entries that map values to themselves.
+struct AlmanacMap {
+	source: String,
+	destination: String,
+	map: PiecewiseMap,
+	coverage: Vec<Range<i64>>,
+}
+
+impl AlmanacMap {
+	/// Gets the gaps between the source ranges of the entries, where values fall back to mapping to themselves.
+	fn gaps(&self) -> Vec<Range<i64>> {
+		self.coverage
+			.windows(2)
+			.map(|pair| pair[0].end..pair[1].start)
+			.collect()
+	}
+}
+
+struct Almanac {
+	seeds: Vec<Range<i64>>,
+	maps: Vec<AlmanacMap>,
+}
+
+fn merge_ranges(mut ranges: Vec<Range<i64>>) -> Vec<Range<i64>> {
+	ranges.retain(|range| !range.is_empty());
+	ranges.sort_unstable_by_key(|range| range.start);
+	let mut merged: Vec<Range<i64>> = Vec::new();
+	for range in ranges {
+		match merged.last_mut() {
+			Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+			_ => merged.push(range),
+		}
+	}
+	merged
+}
+
+impl Almanac {
+	fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+		let mut seeds: Option<Vec<Range<i64>>> = None;
+		let mut raw_maps: Vec<(String, String, Vec<Segment>)> = Vec::new();
+
+		for line in input.lines().filter(|s| !s.is_empty()) {
+			if let Some(seed_list) = line.strip_prefix("seeds: ") {
+				let seed_list: Result<Vec<i64>, _> = seed_list.split(' ').map(|n| n.parse()).collect();
+				let seed_list = seed_list?;
+				if seed_list.len() % 2 != 0 {
+					return Err("Seeds must be given as start and length pairs".into());
 				}
-				let mut number_iter = line.split(' ').map(|n| n.parse().unwrap());
-				let destination: i64 = number_iter.next().unwrap();
-				let start: i64 = number_iter.next().unwrap();
-				let range: i64 = number_iter.next().unwrap();
-				assert!(number_iter.next().is_none());
-
-				let modify_by = start - destination;
-
-				let map_ref = match parse_location {
-					ParseLocation::Seeds => unreachable!(),
-					ParseLocation::SeedSoil => &mut seed_soil_map,
-					ParseLocation::SoilFertilizer => &mut soil_fertilizer_map,
-					ParseLocation::FertilizerWater => &mut fertilizer_water_map,
-					ParseLocation::WaterLight => &mut water_light_map,
-					ParseLocation::LightTemp => &mut light_temperature_map,
-					ParseLocation::TempHumidity => &mut temperature_humidity_map,
-					ParseLocation::HumidityLocation => &mut humidity_location_map,
+				seeds = Some(seed_list.chunks(2).map(|pair| pair[0]..(pair[0] + pair[1])).collect());
+			} else if let Some(header) = line.strip_suffix(" map:") {
+				let Some((source, destination)) = header.split_once("-to-") else {
+					return Err(format!("Invalid map header: {}", line).into());
+				};
+				raw_maps.push((source.to_string(), destination.to_string(), Vec::new()));
+			} else {
+				let Some((_, _, entries)) = raw_maps.last_mut() else {
+					return Err(format!("Map entry outside of a map: {}", line).into());
 				};
-				map_ref.push((destination..(destination + range), modify_by));
+				let numbers: Result<Vec<i64>, _> = line.split(' ').map(|n| n.parse()).collect();
+				let numbers = numbers?;
+				let [destination, start, range] = numbers[..] else {
+					return Err(format!("Map entries need exactly three numbers: {}", line).into());
+				};
+				entries.push((start..(start + range), destination - start));
+			}
+		}
+
+		let mut maps: Vec<AlmanacMap> = Vec::new();
+		for (source, destination, entries) in raw_maps {
+			let coverage = merge_ranges(entries.iter().map(|(range, _)| range.clone()).collect());
+			let map = match PiecewiseMap::new(entries) {
+				Ok(map) => map,
+				Err(error) => return Err(format!("Invalid {}-to-{} map: {}", source, destination, error).into()),
+			};
+			maps.push(AlmanacMap {
+				source,
+				destination,
+				map,
+				coverage,
+			});
+		}
+
+		let Some(seeds) = seeds else {
+			return Err("Almanac has no seeds".into());
+		};
+		let almanac = Self { seeds, maps };
+		almanac.validate()?;
+		Ok(almanac)
+	}
+
+	/// Ensures the maps form a DAG of categories with at most one map between any two categories.
+	fn validate(&self) -> Result<(), Box<dyn Error>> {
+		let mut edges: HashSet<(&str, &str)> = HashSet::new();
+		for map in self.maps.iter() {
+			if !edges.insert((&map.source, &map.destination)) {
+				return Err(format!("Duplicate {}-to-{} map", map.source, map.destination).into());
+			}
+		}
+
+		let mut incoming_counts: HashMap<&str, usize> = HashMap::new();
+		for map in self.maps.iter() {
+			incoming_counts.entry(&map.source).or_insert(0);
+			*incoming_counts.entry(&map.destination).or_insert(0) += 1;
+		}
+		let mut ready: Vec<&str> = incoming_counts
+			.iter()
+			.filter(|(_, count)| **count == 0)
+			.map(|(category, _)| *category)
+			.collect();
+		let mut visited = 0;
+		while let Some(category) = ready.pop() {
+			visited += 1;
+			for map in self.maps.iter().filter(|map| map.source == category) {
+				let count = incoming_counts.get_mut(map.destination.as_str()).unwrap();
+				*count -= 1;
+				if *count == 0 {
+					ready.push(&map.destination);
+				}
+			}
+		}
+		if visited != incoming_counts.len() {
+			return Err("Almanac maps contain a cycle".into());
+		}
+
+		Ok(())
+	}
+
+	/// Finds the shortest chain of maps leading from one category to another.
+	fn path(&self, from: &str, to: &str) -> Option<Vec<&AlmanacMap>> {
+		let mut previous: HashMap<&str, &AlmanacMap> = HashMap::new();
+		let mut queue: VecDeque<&str> = VecDeque::new();
+		queue.push_back(from);
+		while let Some(category) = queue.pop_front() {
+			if category == to {
+				let mut path: Vec<&AlmanacMap> = Vec::new();
+				let mut current = to;
+				while current != from {
+					let map = previous[current];
+					path.push(map);
+					current = &map.source;
+				}
+				path.reverse();
+				return Some(path);
+			}
+			for map in self.maps.iter().filter(|map| map.source == category) {
+				if map.destination != from && !previous.contains_key(map.destination.as_str()) {
+					previous.insert(&map.destination, map);
+					queue.push_back(&map.destination);
+				}
 			}
 		}
+		None
+	}
+
+	/// Flattens the chain of maps between two categories into one map.
+	fn composed(&self, from: &str, to: &str) -> Result<PiecewiseMap, Box<dyn Error>> {
+		let Some(path) = self.path(from, to) else {
+			return Err(format!("No chain of maps leads from {} to {}", from, to).into());
+		};
+		Ok(path
+			.iter()
+			.fold(PiecewiseMap::identity(), |composed, map| composed.compose(&map.map)))
+	}
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+	let mut from = String::from("seed");
+	let mut to = String::from("location");
+	let mut values: Vec<i64> = Vec::new();
+	let mut ranges: Vec<Range<i64>> = Vec::new();
+	let mut show_intervals = false;
+	let mut show_table = false;
+	let mut invert = false;
+	let mut strict = false;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--from" => from = args.next().ok_or("--from requires a category")?,
+			"--to" => to = args.next().ok_or("--to requires a category")?,
+			"--intervals" => show_intervals = true,
+			"--table" => show_table = true,
+			"--invert" => invert = true,
+			"--strict" => strict = true,
+			value => match value.split_once("..") {
+				Some((start, end)) => ranges.push(start.parse()?..end.parse()?),
+				None => values.push(value.parse()?),
+			},
+		}
 	}
 
-	let mut current_location = 0;
-	'location: loop {
-		let humidity = map_num(&humidity_location_map, current_location);
-		let temperature = map_num(&temperature_humidity_map, humidity);
-		let light = map_num(&light_temperature_map, temperature);
-		let water = map_num(&water_light_map, light);
-		let fertilizer = map_num(&fertilizer_water_map, water);
-		let soil = map_num(&soil_fertilizer_map, fertilizer);
-		let seed = map_num(&seed_soil_map, soil);
+	let almanac = {
+		let input = fs::read_to_string("input.txt")?;
+		Almanac::parse(&input)?
+	};
 
-		for seed_range in seeds.iter() {
-			if seed_range.contains(&seed) {
-				break 'location;
+	if strict {
+		for almanac_map in almanac.maps.iter() {
+			if let Some(gap) = almanac_map.gaps().first() {
+				return Err(format!(
+					"Invalid {}-to-{} map: source ranges leave a gap at {}..{}",
+					almanac_map.source, almanac_map.destination, gap.start, gap.end
+				)
+				.into());
 			}
 		}
+	}
 
-		current_location += 1;
+	let composed = almanac.composed(&from, &to)?;
+	let (composed, from, to) = if invert {
+		(composed.inverse()?, to, from)
+	} else {
+		(composed, from, to)
+	};
+
+	if show_table {
+		print!("{}", composed);
 	}
 
-	println!("{}", current_location);
+	if !values.is_empty() || !ranges.is_empty() {
+		for value in values {
+			println!("{} {} -> {} {}", from, value, to, composed.apply(value));
+		}
+		for range in ranges {
+			let mapped: Vec<String> = composed
+				.apply_ranges(std::slice::from_ref(&range))
+				.iter()
+				.map(|mapped_range| format!("{}..{}", mapped_range.start, mapped_range.end))
+				.collect();
+			println!(
+				"{} {}..{} -> {} {}",
+				from,
+				range.start,
+				range.end,
+				to,
+				mapped.join(", ")
+			);
+		}
+	} else if !show_table {
+		if from != "seed" {
+			return Err(format!("Values to translate are required when translating from {}", from).into());
+		}
+		let locations = composed.apply_ranges(&almanac.seeds);
+		if show_intervals {
+			for location_range in locations.iter() {
+				println!("{}..{}", location_range.start, location_range.end);
+			}
+		}
+
+		let Some(closest_location) = locations.first() else {
+			return Err("No seeds to translate".into());
+		};
+		println!("{}", closest_location.start);
+	}
 
 	Ok(())
 }
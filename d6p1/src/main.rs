@@ -1,12 +1,255 @@
+use std::env;
 use std::error::Error;
 use std::fs;
 
+use num::{BigUint, ToPrimitive};
+
 struct EventRecord {
-	time: u32,
-	distance: u32,
+	time: u128,
+	distance: u128,
+}
+
+impl EventRecord {
+	fn beaten_by(&self, button_hold_time: u128) -> bool {
+		match button_hold_time.checked_mul(self.time - button_hold_time) {
+			Some(distance) => distance > self.distance,
+			None => true,
+		}
+	}
+
+	/// Finds the shortest button hold time that beats the record.
+	fn lowest_winning_hold(&self) -> Option<u128> {
+		let half_time = self.time / 2;
+		if !self.beaten_by(half_time) {
+			return None;
+		}
+
+		// The hold time h wins when h^2 - time * h + distance < 0, so the lowest winner sits just above the smaller
+		// root of that quadratic.
+		let discriminant = self
+			.time
+			.checked_mul(self.time)
+			.and_then(|time_squared| time_squared.checked_sub(self.distance.checked_mul(4)?));
+		let mut button_hold_time = match discriminant {
+			Some(discriminant) => ((self.time - isqrt(discriminant)) / 2).min(half_time),
+			None => {
+				let mut low = 0;
+				let mut high = half_time;
+				while low < high {
+					let mid = low + (high - low) / 2;
+					if self.beaten_by(mid) {
+						high = mid;
+					} else {
+						low = mid + 1;
+					}
+				}
+				low
+			}
+		};
+
+		while button_hold_time > 0 && self.beaten_by(button_hold_time - 1) {
+			button_hold_time -= 1;
+		}
+		while !self.beaten_by(button_hold_time) {
+			button_hold_time += 1;
+		}
+		Some(button_hold_time)
+	}
+
+	fn ways_to_beat(&self) -> u128 {
+		match self.lowest_winning_hold() {
+			Some(button_hold_time) => self.time - 2 * button_hold_time + 1,
+			None => 0,
+		}
+	}
+}
+
+/// A way of turning button hold time into distance travelled. Distance must rise and then fall as hold time increases.
+/// Distances are exact, as they can pass `u128::MAX` even when the race time fits.
+trait ChargeModel {
+	fn distance(&self, time: u128, button_hold_time: u128) -> BigUint;
+
+	fn analyse(&self, record: &EventRecord) -> RaceAnalysis {
+		analyse_race(record, |button_hold_time| self.distance(record.time, button_hold_time))
+	}
+}
+
+struct LinearCharge;
+
+impl ChargeModel for LinearCharge {
+	fn distance(&self, time: u128, button_hold_time: u128) -> BigUint {
+		BigUint::from(button_hold_time) * (time - button_hold_time)
+	}
+
+	/// Uses the closed-form solver, as distance is symmetric about half the race time.
+	fn analyse(&self, record: &EventRecord) -> RaceAnalysis {
+		let optimal_hold = record.time / 2;
+		RaceAnalysis {
+			winning_holds: record
+				.lowest_winning_hold()
+				.map(|button_hold_time| (button_hold_time, record.time - button_hold_time)),
+			optimal_hold,
+			best_distance: self.distance(record.time, optimal_hold),
+		}
+	}
+}
+
+struct QuadraticCharge;
+
+impl ChargeModel for QuadraticCharge {
+	fn distance(&self, time: u128, button_hold_time: u128) -> BigUint {
+		BigUint::from(button_hold_time) * button_hold_time * (time - button_hold_time)
+	}
+}
+
+struct CappedCharge {
+	max_speed: u128,
+}
+
+impl ChargeModel for CappedCharge {
+	fn distance(&self, time: u128, button_hold_time: u128) -> BigUint {
+		BigUint::from(button_hold_time.min(self.max_speed)) * (time - button_hold_time)
+	}
+}
+
+struct PenalisedCharge {
+	penalty: u128,
+}
+
+impl ChargeModel for PenalisedCharge {
+	fn distance(&self, time: u128, button_hold_time: u128) -> BigUint {
+		let travel_time = (time - button_hold_time).saturating_sub(self.penalty);
+		BigUint::from(button_hold_time) * travel_time
+	}
+}
+
+fn parse_charge_model(description: &str) -> Result<Box<dyn ChargeModel>, Box<dyn Error>> {
+	let (name, parameter) = match description.split_once(':') {
+		Some((name, parameter)) => (name, Some(parameter)),
+		None => (description, None),
+	};
+	let model: Box<dyn ChargeModel> = match (name, parameter) {
+		("linear", None) => Box::new(LinearCharge),
+		("quadratic", None) => Box::new(QuadraticCharge),
+		("cap", Some(max_speed)) => Box::new(CappedCharge {
+			max_speed: max_speed.parse()?,
+		}),
+		("penalty", Some(penalty)) => Box::new(PenalisedCharge {
+			penalty: penalty.parse()?,
+		}),
+		_ => return Err(format!("Unknown charge model: {}", description).into()),
+	};
+	Ok(model)
+}
+
+struct RaceAnalysis {
+	winning_holds: Option<(u128, u128)>,
+	optimal_hold: u128,
+	best_distance: BigUint,
+}
+
+impl RaceAnalysis {
+	fn ways_to_beat(&self) -> u128 {
+		match self.winning_holds {
+			Some((first, last)) => last - first + 1,
+			None => 0,
+		}
+	}
+}
+
+/// Searches any charge model for its peak and the holds either side of it that beat the record.
+fn analyse_race(record: &EventRecord, distance: impl Fn(u128) -> BigUint) -> RaceAnalysis {
+	let record_distance = BigUint::from(record.distance);
+
+	let mut low = 0;
+	let mut high = record.time;
+	while high - low > 2 {
+		let left = low + (high - low) / 3;
+		let right = high - (high - low) / 3;
+		if distance(left) < distance(right) {
+			low = left + 1;
+		} else {
+			high = right;
+		}
+	}
+	let optimal_hold = (low..=high)
+		.max_by_key(|hold| (distance(*hold), u128::MAX - hold))
+		.unwrap();
+	let best_distance = distance(optimal_hold);
+
+	if best_distance <= record_distance {
+		return RaceAnalysis {
+			winning_holds: None,
+			optimal_hold,
+			best_distance,
+		};
+	}
+
+	let mut low = 0;
+	let mut high = optimal_hold;
+	while low < high {
+		let mid = low + (high - low) / 2;
+		if distance(mid) > record_distance {
+			high = mid;
+		} else {
+			low = mid + 1;
+		}
+	}
+	let first_winning_hold = low;
+
+	let mut low = optimal_hold;
+	let mut high = record.time;
+	while low < high {
+		let mid = high - (high - low) / 2;
+		if distance(mid) > record_distance {
+			low = mid;
+		} else {
+			high = mid - 1;
+		}
+	}
+	let last_winning_hold = low;
+
+	RaceAnalysis {
+		winning_holds: Some((first_winning_hold, last_winning_hold)),
+		optimal_hold,
+		best_distance,
+	}
+}
+
+/// Formats a distance, or notes that it's too large for the `u128` distances records are given in.
+fn describe_distance(distance: &BigUint) -> String {
+	match distance.to_u128() {
+		Some(distance) => format!("{}mm", distance),
+		None => String::from("a distance that exceeds u128"),
+	}
+}
+
+fn isqrt(value: u128) -> u128 {
+	if value < 2 {
+		return value;
+	}
+	let mut root = 1u128 << ((128 - value.leading_zeros()).div_ceil(2));
+	loop {
+		let next_root = (root + value / root) / 2;
+		if next_root >= root {
+			return root;
+		}
+		root = next_root;
+	}
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+	let mut kerned = false;
+	let mut model: Option<Box<dyn ChargeModel>> = None;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--kerned" => kerned = true,
+			"--model" => model = Some(parse_charge_model(&args.next().ok_or("--model requires a value")?)?),
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
 	let records = {
 		let input = fs::read_to_string("input.txt")?;
 
@@ -20,41 +263,65 @@ fn main() -> Result<(), Box<dyn Error>> {
 		times = times.trim();
 		distances = distances.trim();
 
-		let mut records: Vec<EventRecord> = Vec::new();
-		let times_iter = times.split(' ').filter(|s| !s.is_empty());
-		let mut distances_iter = distances.split(' ').filter(|s| !s.is_empty());
+		let times: Vec<&str> = times.split(' ').filter(|s| !s.is_empty()).collect();
+		let distances: Vec<&str> = distances.split(' ').filter(|s| !s.is_empty()).collect();
+		assert_eq!(times.len(), distances.len());
 
-		for time in times_iter {
-			let distance = distances_iter.next().unwrap();
-			let time: u32 = time.parse().unwrap();
-			let distance: u32 = distance.parse().unwrap();
+		let mut records: Vec<EventRecord> = Vec::new();
+		if kerned {
+			let time: u128 = times.concat().parse()?;
+			let distance: u128 = distances.concat().parse()?;
 
 			records.push(EventRecord { time, distance });
+		} else {
+			for (time, distance) in times.iter().zip(distances.iter()) {
+				let time: u128 = time.parse()?;
+				let distance: u128 = distance.parse()?;
+
+				records.push(EventRecord { time, distance });
+			}
 		}
 
 		records
 	};
 
-	let mut ways_to_beat = 1;
-
-	for record in records.iter() {
-		let mut button_hold_time = record.time / 2;
-		let mut ways_to_beat_record = 0;
-		loop {
-			let distance = (record.time - button_hold_time) * button_hold_time;
-			if distance > record.distance {
-				ways_to_beat_record += 1;
-			} else {
-				break;
+	let ways_to_beat = match model {
+		Some(model) => {
+			let mut ways_to_beat = Some(1u128);
+			for record in records.iter() {
+				let analysis = model.analyse(record);
+				let winning_holds = match analysis.winning_holds {
+					Some((first, last)) => format!("{}..={}", first, last),
+					None => String::from("none"),
+				};
+				let record_distance = BigUint::from(record.distance);
+				let (sign, margin) = if analysis.best_distance >= record_distance {
+					('+', &analysis.best_distance - &record_distance)
+				} else {
+					('-', &record_distance - &analysis.best_distance)
+				};
+				let margin = match margin.to_u128() {
+					Some(margin) => format!("{}{}mm", sign, margin),
+					None => String::from("exceeds u128"),
+				};
+				println!(
+					"Race {}ms/{}mm: winning holds {}, optimal hold {} reaching {} (margin {})",
+					record.time,
+					record.distance,
+					winning_holds,
+					analysis.optimal_hold,
+					describe_distance(&analysis.best_distance),
+					margin
+				);
+				ways_to_beat = ways_to_beat.and_then(|product| product.checked_mul(analysis.ways_to_beat()));
 			}
-			button_hold_time -= 1;
-		}
-		ways_to_beat_record *= 2;
-		if record.time % 2 == 0 {
-			ways_to_beat_record -= 1;
+			ways_to_beat
 		}
-		ways_to_beat *= ways_to_beat_record;
-	}
+		None => records
+			.iter()
+			.try_fold(1u128, |product, record| product.checked_mul(record.ways_to_beat())),
+	};
+	let ways_to_beat = ways_to_beat.ok_or("Product of ways to beat each record is too large")?;
 
 	println!("{}", ways_to_beat);
 
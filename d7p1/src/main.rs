@@ -1,92 +1,244 @@
-use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::env;
 use std::error::Error;
 use std::fs;
 
 const CARD_ORDER: [char; 13] = ['A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2'];
+const JOKER_CARD_ORDER: [char; 13] = ['A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J'];
 
-fn cmp_cards(lhs: char, rhs: char) -> Ordering {
-	if lhs == rhs {
-		return Ordering::Equal;
-	}
-
-	let lhs = CARD_ORDER
-		.iter()
-		.enumerate()
-		.find(|(_, card)| **card == lhs)
-		.map(|(index, _)| index)
-		.unwrap();
-	let rhs = CARD_ORDER
-		.iter()
-		.enumerate()
-		.find(|(_, card)| **card == rhs)
-		.map(|(index, _)| index)
-		.unwrap();
+/// A rung on the hand type ladder, described by the card groups needed (largest first) to reach it.
+struct HandType {
+	name: String,
+	groups: Vec<u8>,
+}
 
-	rhs.cmp(&lhs)
+struct RuleSet {
+	card_order: Vec<char>,
+	wild_cards: Vec<char>,
+	hand_size: usize,
+	ladder: Vec<HandType>,
 }
 
-fn hand_type(hand_counts: &[u8]) -> u8 {
-	match hand_counts {
-		[5] => 7,
-		[1, 4] => 6,
-		[2, 3] => 5,
-		[1, 1, 3] => 4,
-		[1, 2, 2] => 3,
-		[1, 1, 1, 2] => 2,
-		[1, 1, 1, 1, 1] => 1,
-		_ => unreachable!(),
+impl RuleSet {
+	fn standard() -> Self {
+		Self {
+			card_order: CARD_ORDER.to_vec(),
+			wild_cards: Vec::new(),
+			hand_size: 5,
+			ladder: vec![
+				HandType {
+					name: String::from("High card"),
+					groups: vec![],
+				},
+				HandType {
+					name: String::from("One pair"),
+					groups: vec![2],
+				},
+				HandType {
+					name: String::from("Two pair"),
+					groups: vec![2, 2],
+				},
+				HandType {
+					name: String::from("Three of a kind"),
+					groups: vec![3],
+				},
+				HandType {
+					name: String::from("Full house"),
+					groups: vec![3, 2],
+				},
+				HandType {
+					name: String::from("Four of a kind"),
+					groups: vec![4],
+				},
+				HandType {
+					name: String::from("Five of a kind"),
+					groups: vec![5],
+				},
+			],
+		}
 	}
-}
 
-#[derive(Eq, PartialEq)]
-struct Hand {
-	cards: [char; 5],
-	bid: usize,
-}
+	fn jokers() -> Self {
+		Self {
+			card_order: JOKER_CARD_ORDER.to_vec(),
+			wild_cards: vec!['J'],
+			..Self::standard()
+		}
+	}
 
-impl Ord for Hand {
-	fn cmp(&self, other: &Self) -> Ordering {
-		let mut lhs_counts: HashMap<char, u8> = HashMap::new();
-		let mut rhs_counts: HashMap<char, u8> = HashMap::new();
+	fn card_strength(&self, card: char) -> Option<usize> {
+		self.card_order
+			.iter()
+			.position(|order_card| *order_card == card)
+			.map(|index| self.card_order.len() - index)
+	}
+
+	fn card_strengths(&self) -> HashMap<char, u128> {
+		self.card_order
+			.iter()
+			.map(|card| (*card, self.card_strength(*card).unwrap() as u128))
+			.collect()
+	}
 
-		for card in self.cards.iter() {
-			*lhs_counts.entry(*card).or_default() += 1;
+	/// Ensures every possible sort key fits in a `u128`.
+	fn check_sort_key_width(&self) -> Result<(), Box<dyn Error>> {
+		let base = self.card_order.len() as u128 + 1;
+		let mut key_limit = Some(self.ladder.len() as u128);
+		for _ in 0..self.hand_size {
+			key_limit = key_limit.and_then(|limit| limit.checked_mul(base));
 		}
-		for card in other.cards.iter() {
-			*rhs_counts.entry(*card).or_default() += 1;
+		match key_limit {
+			Some(_) => Ok(()),
+			None => Err(format!("Hands of {} cards are too large to rank", self.hand_size).into()),
+		}
+	}
+
+	/// Gets each group of matching non-wild cards, largest (then strongest) first, along with the number of wild
+	/// cards.
+	fn card_groups(&self, cards: &[char]) -> (Vec<(char, u8)>, u8) {
+		let mut counts: HashMap<char, u8> = HashMap::new();
+		let mut wild_count = 0;
+		for card in cards.iter() {
+			if self.wild_cards.contains(card) {
+				wild_count += 1;
+			} else {
+				*counts.entry(*card).or_default() += 1;
+			}
 		}
 
-		let mut lhs_count_totals: Vec<u8> = lhs_counts.values().copied().collect();
-		let mut rhs_count_totals: Vec<u8> = rhs_counts.values().copied().collect();
+		let mut groups: Vec<(char, u8)> = counts.into_iter().collect();
+		groups.sort_unstable_by(|(lhs_card, lhs_count), (rhs_card, rhs_count)| {
+			rhs_count
+				.cmp(lhs_count)
+				.then_with(|| self.card_strength(*rhs_card).cmp(&self.card_strength(*lhs_card)))
+		});
+		(groups, wild_count)
+	}
 
-		lhs_count_totals.sort_unstable();
-		rhs_count_totals.sort_unstable();
+	/// Gets the index in the ladder of the best type the hand can reach, using wild cards wherever they help most.
+	fn hand_type(&self, cards: &[char]) -> usize {
+		let (groups, wild_count) = self.card_groups(cards);
+		self.ladder
+			.iter()
+			.rposition(|hand_type| {
+				let wilds_needed: u8 = hand_type
+					.groups
+					.iter()
+					.enumerate()
+					.map(|(index, needed)| {
+						needed.saturating_sub(groups.get(index).map(|(_, count)| *count).unwrap_or(0))
+					})
+					.sum();
+				wilds_needed <= wild_count
+			})
+			.unwrap_or(0)
+	}
 
-		// Types are assigned a numeric value from 5 of a Kind (7) down to High Card (1)
-		let lhs_type = hand_type(&lhs_count_totals);
-		let rhs_type = hand_type(&rhs_count_totals);
+	/// Replaces each wild card with a concrete card that reaches the hand's best type.
+	fn substitute_wild_cards(&self, cards: &[char]) -> Vec<char> {
+		let (groups, mut wild_count) = self.card_groups(cards);
+		let hand_type = &self.ladder[self.hand_type(cards)];
+		let mut unused_cards = self
+			.card_order
+			.iter()
+			.filter(|card| !self.wild_cards.contains(card) && !cards.contains(card));
 
-		lhs_type.cmp(&rhs_type).then_with(|| {
-			for (lhs, rhs) in self.cards.iter().zip(&other.cards) {
-				match cmp_cards(*lhs, *rhs) {
-					Ordering::Less => return Ordering::Less,
-					Ordering::Greater => return Ordering::Greater,
-					_ => (),
+		let mut replacements: Vec<char> = Vec::new();
+		for (index, needed) in hand_type.groups.iter().enumerate() {
+			let (card, count) = match groups.get(index) {
+				Some(group) => *group,
+				None => match unused_cards.next() {
+					Some(card) => (*card, 0),
+					None => break,
+				},
+			};
+			for _ in count..*needed {
+				if wild_count > 0 {
+					replacements.push(card);
+					wild_count -= 1;
 				}
 			}
-			Ordering::Equal
+		}
+		let spare_card = groups
+			.first()
+			.map(|(card, _)| *card)
+			.or(replacements.first().copied())
+			.or(self
+				.card_order
+				.iter()
+				.find(|card| !self.wild_cards.contains(card))
+				.copied());
+		if let Some(spare_card) = spare_card {
+			for _ in 0..wild_count {
+				replacements.push(spare_card);
+			}
+		}
+
+		let mut replacements = replacements.into_iter();
+		cards
+			.iter()
+			.map(|card| {
+				if self.wild_cards.contains(card) {
+					replacements.next().unwrap_or(*card)
+				} else {
+					*card
+				}
+			})
+			.collect()
+	}
+
+	/// Reduces a hand to a single number ordered first by hand type, then by the strength of each card in turn.
+	fn sort_key(&self, card_strengths: &HashMap<char, u128>, cards: &[char]) -> u128 {
+		let base = self.card_order.len() as u128 + 1;
+		cards.iter().fold(self.hand_type(cards) as u128, |key, card| {
+			key * base + card_strengths[card]
 		})
 	}
 }
 
-impl PartialOrd for Hand {
-	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-		Some(self.cmp(other))
+struct Hand {
+	cards: Vec<char>,
+	bid: usize,
+	sort_key: u128,
+}
+
+/// Parses a ladder given weakest first as `;`-separated rungs of `,`-separated group sizes, such as `;2;2,2;3`.
+fn parse_ladder(description: &str) -> Result<Vec<HandType>, Box<dyn Error>> {
+	let mut ladder: Vec<HandType> = Vec::new();
+	for rung in description.split(';') {
+		let groups: Result<Vec<u8>, _> = rung.split(',').filter(|s| !s.is_empty()).map(|s| s.parse()).collect();
+		let mut groups = groups?;
+		groups.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+		let group_names: Vec<String> = groups.iter().map(|group| group.to_string()).collect();
+		let name = if groups.is_empty() {
+			String::from("No groups")
+		} else {
+			format!("Groups of {}", group_names.join(", "))
+		};
+		ladder.push(HandType { name, groups });
 	}
+	Ok(ladder)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+	let mut rules = RuleSet::standard();
+	let mut explain = false;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--jokers" => rules = RuleSet::jokers(),
+			"--explain" => explain = true,
+			"--order" => rules.card_order = args.next().ok_or("--order requires a value")?.chars().collect(),
+			"--wild" => rules.wild_cards = args.next().ok_or("--wild requires a value")?.chars().collect(),
+			"--ladder" => rules.ladder = parse_ladder(&args.next().ok_or("--ladder requires a value")?)?,
+			"--hand-size" => rules.hand_size = args.next().ok_or("--hand-size requires a value")?.parse()?,
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
+	rules.check_sort_key_width()?;
+	let card_strengths = rules.card_strengths();
+
 	let mut hands = {
 		let input = fs::read_to_string("input.txt")?;
 
@@ -98,21 +250,59 @@ fn main() -> Result<(), Box<dyn Error>> {
 			assert!(line_parts.next().is_none());
 
 			let bid: usize = bid.parse()?;
-			let mut hand_chars = hand.chars();
-			let hand = [
-				hand_chars.next().unwrap(),
-				hand_chars.next().unwrap(),
-				hand_chars.next().unwrap(),
-				hand_chars.next().unwrap(),
-				hand_chars.next().unwrap(),
-			];
-			hands.push(Hand { cards: hand, bid });
+			let cards: Vec<char> = hand.chars().collect();
+			if cards.len() != rules.hand_size {
+				return Err(format!("Hand {} doesn't have {} cards", hand, rules.hand_size).into());
+			}
+			if let Some(card) = cards.iter().find(|card| !card_strengths.contains_key(card)) {
+				return Err(format!("Hand {} has card {} which isn't in the card order", hand, card).into());
+			}
+			let sort_key = rules.sort_key(&card_strengths, &cards);
+			hands.push(Hand { cards, bid, sort_key });
 		}
 
 		hands
 	};
 
-	hands.sort_unstable();
+	hands.sort_unstable_by_key(|hand| hand.sort_key);
+
+	if explain {
+		for (index, hand) in hands.iter().enumerate() {
+			let hand_type = &rules.ladder[rules.hand_type(&hand.cards)];
+			let cards: String = hand.cards.iter().collect();
+			let mut explanation = format!("Rank {}: {} ({}, bid {})", index + 1, cards, hand_type.name, hand.bid);
+
+			if hand.cards.iter().any(|card| rules.wild_cards.contains(card)) {
+				let substituted: String = rules.substitute_wild_cards(&hand.cards).into_iter().collect();
+				explanation.push_str(&format!(", played as {}", substituted));
+			}
+
+			if let Some(previous_hand) = index.checked_sub(1).map(|previous_index| &hands[previous_index]) {
+				let previous_type = rules.hand_type(&previous_hand.cards);
+				if previous_type == rules.hand_type(&hand.cards) {
+					let tie_break_position = hand
+						.cards
+						.iter()
+						.zip(previous_hand.cards.iter())
+						.position(|(card, previous_card)| card != previous_card);
+					match tie_break_position {
+						Some(position) => explanation.push_str(&format!(
+							", beats rank {} at card {} ({} over {})",
+							index,
+							position + 1,
+							hand.cards[position],
+							previous_hand.cards[position]
+						)),
+						None => explanation.push_str(&format!(", tied with rank {}", index)),
+					}
+				} else {
+					explanation.push_str(&format!(", beats rank {} by type", index));
+				}
+			}
+
+			println!("{}", explanation);
+		}
+	}
 
 	let total: usize = hands
 		.into_iter()
@@ -1,5 +1,6 @@
-use num::integer::lcm;
-use std::collections::HashMap;
+use num::integer::Integer;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::error::Error;
 use std::fs;
 
@@ -9,13 +10,189 @@ enum Direction {
 	Right,
 }
 
-#[derive(Debug)]
-struct StartNodeData {
-	to_end: Vec<u64>,
-	cycle_offset: u64,
+impl Direction {
+	fn index(&self) -> usize {
+		match self {
+			Self::Left => 0,
+			Self::Right => 1,
+		}
+	}
+}
+
+/// The network with node names interned to indices into a flat table of left and right links.
+struct Network {
+	names: Vec<String>,
+	links: Vec<[u32; 2]>,
+}
+
+impl Network {
+	fn new(map: &HashMap<String, (String, String)>) -> Self {
+		let mut names: Vec<String> = map.keys().cloned().collect();
+		names.sort_unstable();
+		let ids: HashMap<&str, u32> = names
+			.iter()
+			.enumerate()
+			.map(|(id, name)| (name.as_str(), id as u32))
+			.collect();
+		let links = names
+			.iter()
+			.map(|name| {
+				let (left_node, right_node) = &map[name];
+				[ids[left_node.as_str()], ids[right_node.as_str()]]
+			})
+			.collect();
+		Self { names, links }
+	}
+
+	fn next(&self, node: u32, direction: Direction) -> u32 {
+		self.links[node as usize][direction.index()]
+	}
+
+	fn is_start(&self, node: u32) -> bool {
+		self.names[node as usize].ends_with('A')
+	}
+
+	fn is_end(&self, node: u32) -> bool {
+		self.names[node as usize].ends_with('Z')
+	}
+
+	/// Renders the network as Graphviz DOT, highlighting start and end nodes and the links each ghost cycles through.
+	fn to_dot(&self, cycle_links: &HashSet<(u32, usize)>) -> String {
+		let mut dot = String::from("digraph network {\n");
+		for (node, name) in self.names.iter().enumerate() {
+			let node = node as u32;
+			let style = if self.is_start(node) {
+				" [style=filled, fillcolor=palegreen]"
+			} else if self.is_end(node) {
+				" [style=filled, fillcolor=salmon]"
+			} else {
+				""
+			};
+			dot.push_str(&format!("\t\"{}\"{};\n", name, style));
+		}
+		for (node, links) in self.links.iter().enumerate() {
+			for (direction_index, (label, destination)) in ["L", "R"].iter().zip(links.iter()).enumerate() {
+				let style = if cycle_links.contains(&(node as u32, direction_index)) {
+					", color=blue, penwidth=2"
+				} else {
+					""
+				};
+				dot.push_str(&format!(
+					"\t\"{}\" -> \"{}\" [label=\"{}\"{}];\n",
+					self.names[node], self.names[*destination as usize], label, style
+				));
+			}
+		}
+		dot.push_str("}\n");
+		dot
+	}
+}
+
+/// The steps at which one ghost stands on an end node: every step in `transient_hits`, then every step congruent to
+/// one of `cycle_hits` modulo `cycle_length` from `cycle_start` onwards.
+#[derive(Clone)]
+struct EndSteps {
+	transient_hits: Vec<u64>,
+	cycle_start: u64,
+	cycle_length: u64,
+	cycle_hits: Vec<u64>,
+}
+
+impl EndSteps {
+	fn contains(&self, step: u64) -> bool {
+		if step < self.cycle_start {
+			self.transient_hits.contains(&step)
+		} else {
+			let cycle_position = self.cycle_start + (step - self.cycle_start) % self.cycle_length;
+			self.cycle_hits.contains(&cycle_position)
+		}
+	}
+}
+
+/// Solves x = a (mod m) and x = b (mod n) for moduli that need not be coprime, giving `Ok(None)` when there's no
+/// solution.
+fn combine_congruences(a: i128, m: i128, b: i128, n: i128) -> Result<Option<(i128, i128)>, Box<dyn Error>> {
+	let gcd_data = m.extended_gcd(&n);
+	let gcd = gcd_data.gcd;
+	if (b - a) % gcd != 0 {
+		return Ok(None);
+	}
+	let too_large = "Combined cycle length is too large";
+	let modulus = (m / gcd).checked_mul(n).ok_or(too_large)?;
+	let step = ((b - a) / gcd)
+		.checked_mul(gcd_data.x)
+		.ok_or(too_large)?
+		.rem_euclid(n / gcd);
+	let combined = m
+		.checked_mul(step)
+		.and_then(|offset| offset.checked_add(a))
+		.ok_or(too_large)?;
+	Ok(Some((combined.rem_euclid(modulus), modulus)))
+}
+
+/// Finds the first step at which every ghost stands on an end node at once.
+/// Gives `Ok(None)` if they never do, and an error if the step is too large to count.
+fn first_common_step(ghosts: &[EndSteps]) -> Result<Option<u64>, Box<dyn Error>> {
+	let Some(first_ghost) = ghosts.first() else {
+		return Ok(None);
+	};
+
+	// A common step before every ghost has entered its cycle must be a transient hit of at least one ghost
+	let transient_answer = ghosts
+		.iter()
+		.flat_map(|ghost| ghost.transient_hits.iter())
+		.filter(|step| ghosts.iter().all(|ghost| ghost.contains(**step)))
+		.min()
+		.copied();
+
+	let mut residues: Vec<i128> = first_ghost.cycle_hits.iter().map(|hit| *hit as i128).collect();
+	let mut modulus = first_ghost.cycle_length as i128;
+	for ghost in ghosts.iter().skip(1) {
+		let mut next_residues: Vec<i128> = Vec::new();
+		let mut next_modulus = modulus;
+		for residue in residues.iter() {
+			for hit in ghost.cycle_hits.iter() {
+				if let Some((combined, combined_modulus)) =
+					combine_congruences(*residue, modulus, *hit as i128, ghost.cycle_length as i128)?
+				{
+					next_residues.push(combined);
+					next_modulus = combined_modulus;
+				}
+			}
+		}
+		next_residues.sort_unstable();
+		next_residues.dedup();
+		residues = next_residues;
+		modulus = next_modulus;
+	}
+
+	let all_cycling_from = ghosts.iter().map(|ghost| ghost.cycle_start).max().unwrap() as i128;
+	let cycle_answer = residues
+		.iter()
+		.map(|residue| {
+			let behind = (all_cycling_from - residue).max(0);
+			residue + (behind + modulus - 1) / modulus * modulus
+		})
+		.min()
+		.map(|step| u64::try_from(step).map_err(|_| format!("First common step {} is too large to count", step)))
+		.transpose()?;
+
+	Ok(match (transient_answer, cycle_answer) {
+		(Some(transient), Some(cycle)) => Some(transient.min(cycle)),
+		(transient, cycle) => transient.or(cycle),
+	})
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+	let mut dot_path: Option<String> = None;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--dot" => dot_path = Some(args.next().ok_or("--dot requires an output path")?),
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
 	let (directions, map) = {
 		let input = fs::read_to_string("input.txt")?;
 
@@ -51,57 +228,52 @@ fn main() -> Result<(), Box<dyn Error>> {
 		(directions, map)
 	};
 
-	let current_nodes: Vec<&String> = map.keys().filter(|k| k.ends_with('A')).collect();
-	let mut node_data: Vec<StartNodeData> = Vec::new();
-	for node in current_nodes {
-		let mut directions_iter = directions.iter().cycle();
-		let mut steps_taken: u64 = 0;
+	let network = Network::new(&map);
 
-		let mut first_z_node = None;
-		let mut end_z_positions: Vec<u64> = Vec::new();
-
-		let mut current_node = node;
+	let mut ghosts: Vec<EndSteps> = Vec::new();
+	let mut cycle_links: HashSet<(u32, usize)> = HashSet::new();
+	for start_node in (0..network.names.len() as u32).filter(|node| network.is_start(*node)) {
+		let mut seen_states: Vec<u64> = vec![u64::MAX; network.names.len() * directions.len()];
+		let mut path: Vec<(u32, usize)> = Vec::new();
+		let mut end_hits: Vec<u64> = Vec::new();
+		let mut current_node = start_node;
+		let mut steps_taken: u64 = 0;
 
-		loop {
-			steps_taken += 1;
-			let next_direction = *directions_iter.next().unwrap();
-			let (left_node, right_node) = map.get(current_node).unwrap();
-			current_node = match next_direction {
-				Direction::Left => left_node,
-				Direction::Right => right_node,
-			};
-			if current_node.ends_with('Z') {
-				match first_z_node {
-					Some((node, _)) => {
-						end_z_positions.push(steps_taken);
-						if node == current_node {
-							break;
-						}
-					}
-					None => first_z_node = Some((current_node, steps_taken)),
-				}
+		let cycle_start = loop {
+			let direction_index = steps_taken as usize % directions.len();
+			let state = current_node as usize * directions.len() + direction_index;
+			if seen_states[state] != u64::MAX {
+				break seen_states[state];
+			}
+			seen_states[state] = steps_taken;
+			if network.is_end(current_node) {
+				end_hits.push(steps_taken);
 			}
-		}
 
-		let (_, cycle_offset) = first_z_node.unwrap();
+			let direction = directions[direction_index];
+			path.push((current_node, direction.index()));
+			current_node = network.next(current_node, direction);
+			steps_taken += 1;
+		};
+		cycle_links.extend(path.into_iter().skip(cycle_start as usize));
 
-		node_data.push(StartNodeData {
-			to_end: end_z_positions,
-			cycle_offset,
+		let (cycle_hits, transient_hits) = end_hits.into_iter().partition(|step| *step >= cycle_start);
+		ghosts.push(EndSteps {
+			transient_hits,
+			cycle_start,
+			cycle_length: steps_taken - cycle_start,
+			cycle_hits,
 		});
 	}
 
-	let mut fewest_steps = 1;
-	for node in node_data.iter() {
-		println!("{:?}", node);
-		let mut fewest_steps_for_node = 1;
-		for position in node.to_end.iter() {
-			fewest_steps_for_node = lcm(fewest_steps_for_node, *position - node.cycle_offset);
-		}
-		fewest_steps = lcm(fewest_steps, fewest_steps_for_node);
+	if let Some(path) = dot_path {
+		fs::write(path, network.to_dot(&cycle_links))?;
 	}
 
-	println!("{}", fewest_steps);
+	match first_common_step(&ghosts)? {
+		Some(fewest_steps) => println!("{}", fewest_steps),
+		None => println!("never"),
+	}
 
 	Ok(())
 }
@@ -1,27 +1,161 @@
+use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs;
 
-fn extend_sequence(sequence: &[i32]) -> i32 {
-	let mut differences: Vec<i32> = Vec::new();
+fn gcd(lhs: i128, rhs: i128) -> i128 {
+	if rhs == 0 {
+		lhs.abs()
+	} else {
+		gcd(rhs, lhs % rhs)
+	}
+}
+
+#[derive(Clone, Copy)]
+struct Rational {
+	numerator: i128,
+	denominator: i128,
+}
+
+impl Rational {
+	fn new(numerator: i128, denominator: i128) -> Self {
+		let divisor = gcd(numerator, denominator) * denominator.signum();
+		Self {
+			numerator: numerator / divisor,
+			denominator: denominator / divisor,
+		}
+	}
 
-	for value_pair in sequence.windows(2) {
-		differences.push(value_pair[1] - value_pair[0]);
+	fn integer(value: i128) -> Self {
+		Self::new(value, 1)
 	}
 
-	if differences.iter().all(|v| *v == 0) {
-		*sequence.last().unwrap()
-	} else {
-		*sequence.last().unwrap() + extend_sequence(&differences)
+	fn is_zero(&self) -> bool {
+		self.numerator == 0
+	}
+
+	fn add(&self, other: &Self) -> Self {
+		Self::new(
+			self.numerator * other.denominator + other.numerator * self.denominator,
+			self.denominator * other.denominator,
+		)
+	}
+
+	fn mul(&self, other: &Self) -> Self {
+		Self::new(self.numerator * other.numerator, self.denominator * other.denominator)
+	}
+}
+
+impl fmt::Display for Rational {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.denominator == 1 {
+			write!(f, "{}", self.numerator)
+		} else {
+			write!(f, "{}/{}", self.numerator, self.denominator)
+		}
+	}
+}
+
+/// The polynomial passing through every term of a sequence, with the first term at index 0.
+struct Polynomial {
+	leading_differences: Vec<i128>,
+	coefficients: Vec<Rational>,
+}
+
+impl Polynomial {
+	/// Fits the sequence, or gives `None` if the differences never settle to a constant before running out of terms.
+	fn fit(sequence: &[i64]) -> Option<Self> {
+		let mut differences: Vec<i128> = sequence.iter().map(|value| *value as i128).collect();
+		let mut leading_differences: Vec<i128> = Vec::new();
+		loop {
+			if differences.len() < 2 {
+				return None;
+			}
+			leading_differences.push(differences[0]);
+			if differences.iter().all(|value| *value == differences[0]) {
+				break;
+			}
+			differences = differences.windows(2).map(|pair| pair[1] - pair[0]).collect();
+		}
+
+		// Expand the Newton form (the sum of each leading difference times x choose k) into plain coefficients
+		let mut coefficients: Vec<Rational> = vec![Rational::integer(0); leading_differences.len()];
+		let mut falling_factorial: Vec<Rational> = vec![Rational::integer(1)];
+		for (k, difference) in leading_differences.iter().enumerate() {
+			let scale = Rational::integer(*difference);
+			for (power, coefficient) in falling_factorial.iter().enumerate() {
+				coefficients[power] = coefficients[power].add(&coefficient.mul(&scale));
+			}
+
+			let mut next_factorial: Vec<Rational> = vec![Rational::integer(0); falling_factorial.len() + 1];
+			let divisor = Rational::new(1, k as i128 + 1);
+			for (power, coefficient) in falling_factorial.iter().enumerate() {
+				let coefficient = coefficient.mul(&divisor);
+				next_factorial[power + 1] = next_factorial[power + 1].add(&coefficient);
+				next_factorial[power] = next_factorial[power].add(&coefficient.mul(&Rational::integer(-(k as i128))));
+			}
+			falling_factorial = next_factorial;
+		}
+		while coefficients.len() > 1 && coefficients.last().unwrap().is_zero() {
+			coefficients.pop();
+		}
+
+		Some(Self {
+			leading_differences,
+			coefficients,
+		})
+	}
+
+	/// Evaluates the polynomial at any index, or gives `None` if the value doesn't fit in an `i128`.
+	fn at(&self, index: i128) -> Option<i128> {
+		let mut value: i128 = 0;
+		let mut binomial: i128 = 1;
+		for (k, difference) in self.leading_differences.iter().enumerate() {
+			if k > 0 {
+				binomial = binomial.checked_mul(index - k as i128 + 1)? / k as i128;
+			}
+			value = value.checked_add(difference.checked_mul(binomial)?)?;
+		}
+		Some(value)
+	}
+}
+
+impl fmt::Display for Polynomial {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut terms: Vec<String> = Vec::new();
+		for (power, coefficient) in self.coefficients.iter().enumerate().rev() {
+			if coefficient.is_zero() && self.coefficients.len() > 1 {
+				continue;
+			}
+			let term = match power {
+				0 => format!("{}", coefficient),
+				1 => format!("({})x", coefficient),
+				_ => format!("({})x^{}", coefficient, power),
+			};
+			terms.push(term);
+		}
+		write!(f, "{}", terms.join(" + "))
 	}
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+	let mut at: Option<i128> = None;
+	let mut show_coefficients = false;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--at" => at = Some(args.next().ok_or("--at requires an index")?.parse()?),
+			"--coefficients" => show_coefficients = true,
+			other => return Err(format!("Unknown argument: {}", other).into()),
+		}
+	}
+
 	let sequences = {
 		let input = fs::read_to_string("input.txt")?;
 
-		let mut sequences: Vec<Vec<i32>> = Vec::new();
+		let mut sequences: Vec<Vec<i64>> = Vec::new();
 		for line in input.lines() {
-			let mut sequence: Vec<i32> = Vec::new();
+			let mut sequence: Vec<i64> = Vec::new();
 			for num in line.split(' ') {
 				sequence.push(num.parse()?);
 			}
@@ -31,9 +165,25 @@ fn main() -> Result<(), Box<dyn Error>> {
 		sequences
 	};
 
-	let mut next_values_sum = 0;
-	for sequence in sequences.iter() {
-		next_values_sum += extend_sequence(sequence);
+	let mut next_values_sum: i128 = 0;
+	for (line_index, sequence) in sequences.iter().enumerate() {
+		let Some(polynomial) = Polynomial::fit(sequence) else {
+			return Err(format!("Sequence on line {} is not polynomial", line_index + 1).into());
+		};
+		let index = at.unwrap_or(sequence.len() as i128);
+		let value = polynomial
+			.at(index)
+			.ok_or_else(|| format!("Value at index {} for line {} is too large", index, line_index + 1))?;
+		if show_coefficients {
+			println!(
+				"Line {}: p(x) = {}, p({}) = {}",
+				line_index + 1,
+				polynomial,
+				index,
+				value
+			);
+		}
+		next_values_sum += value;
 	}
 
 	println!("{}", next_values_sum);